@@ -14,7 +14,10 @@ window.init_auth = async (domain, client_id) => {
     }
 
     if (await auth0.isAuthenticated()) {
-        return await auth0.getUser();
+        return {
+            user: await auth0.getUser(),
+            token: await auth0.getTokenSilently(),
+        };
     }
 }
 
@@ -0,0 +1,83 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::graphql::{
+    self,
+    mutations::{client, invoice, project, time_block, time_entry},
+    queries::{
+        clients_with_projects, 
+        clients_with_projects_with_time_entries, 
+        clients_with_projects_with_time_entries_in_range,
+        clients_with_time_blocks_and_time_entries,
+    },
+};
+
+pub mod graphql_backend;
+pub mod local_storage_backend;
+
+pub use graphql_backend::GraphQLBackend;
+pub use local_storage_backend::LocalStorageBackend;
+
+pub type BackendFuture<T> = Pin<Box<dyn Future<Output = graphql::Result<T>>>>;
+
+// Data operations used by pages. Returned futures don't borrow the backend 
+// so they can be moved into `orders.perform_cmd`.
+pub trait Backend {
+    // ------ Queries ------
+
+    fn clients_with_projects(
+        &self, 
+        args: clients_with_projects::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects::Client>>;
+
+    fn clients_with_projects_with_time_entries(
+        &self, 
+        args: clients_with_projects_with_time_entries::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects_with_time_entries::Client>>;
+
+    fn clients_with_projects_with_time_entries_in_range(
+        &self, 
+        args: clients_with_projects_with_time_entries_in_range::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects_with_time_entries_in_range::Client>>;
+
+    fn clients_with_time_blocks_and_time_entries(
+        &self, 
+        args: clients_with_time_blocks_and_time_entries::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_time_blocks_and_time_entries::Client>>;
+
+    // ------ Client ------
+
+    fn add_client(&self, args: client::add::AddClientArguments) -> BackendFuture<()>;
+    fn rename_client(&self, args: client::rename::RenameClientArguments) -> BackendFuture<()>;
+    fn delete_client(&self, args: client::delete::DeleteClientArguments) -> BackendFuture<()>;
+
+    // ------ Project ------
+
+    fn add_project(&self, args: project::add::AddProjectArguments) -> BackendFuture<()>;
+    fn rename_project(&self, args: project::rename::RenameProjectArguments) -> BackendFuture<()>;
+    fn delete_project(&self, args: project::delete::DeleteProjectArguments) -> BackendFuture<()>;
+
+    // ------ TimeEntry ------
+
+    fn add_time_entry(&self, args: time_entry::add::AddTimeEntryArguments) -> BackendFuture<()>;
+    fn add_time_entries(&self, args: time_entry::add_many::AddTimeEntriesArguments) -> BackendFuture<()>;
+    fn rename_time_entry(&self, args: time_entry::rename::RenameTimeEntryArguments) -> BackendFuture<()>;
+    fn delete_time_entry(&self, args: time_entry::delete::DeleteTimeEntryArguments) -> BackendFuture<()>;
+    fn set_time_entry_times(&self, args: time_entry::set_times::SetTimeEntryTimesArguments) -> BackendFuture<()>;
+    fn set_time_entry_project(&self, args: time_entry::set_project::SetTimeEntryProjectArguments) -> BackendFuture<()>;
+
+    // ------ TimeBlock ------
+
+    fn add_time_block(&self, args: time_block::add::AddTimeBlockArguments) -> BackendFuture<()>;
+    fn rename_time_block(&self, args: time_block::rename::RenameTimeBlockArguments) -> BackendFuture<()>;
+    fn delete_time_block(&self, args: time_block::delete::DeleteTimeBlockArguments) -> BackendFuture<()>;
+    fn set_time_block_duration(&self, args: time_block::set_duration::SetTimeBlockDurationArguments) -> BackendFuture<()>;
+    fn set_time_block_status(&self, args: time_block::set_status::SetTimeBlockStatusArguments) -> BackendFuture<()>;
+
+    // ------ Invoice ------
+
+    fn add_invoice(&self, args: invoice::add::AddInvoiceArguments) -> BackendFuture<()>;
+    fn set_invoice_custom_id(&self, args: invoice::set_custom_id::SetInvoiceCustomIdArguments) -> BackendFuture<()>;
+    fn set_invoice_url(&self, args: invoice::set_url::SetInvoiceUrlArguments) -> BackendFuture<()>;
+    fn delete_invoice(&self, args: invoice::delete::DeleteInvoiceArguments) -> BackendFuture<()>;
+}
@@ -0,0 +1,185 @@
+use cynic::{QueryBuilder, MutationBuilder};
+
+use std::convert::identity;
+
+use crate::graphql::{
+    self,
+    mutations::{client, invoice, project, time_block, time_entry},
+    queries::{
+        clients_with_projects, 
+        clients_with_projects_with_time_entries, 
+        clients_with_projects_with_time_entries_in_range,
+        clients_with_time_blocks_and_time_entries,
+    },
+};
+use super::{Backend, BackendFuture};
+
+pub struct GraphQLBackend {
+    graphql_endpoint: String,
+    token: Option<String>,
+}
+
+impl GraphQLBackend {
+    pub fn new(graphql_endpoint: String, token: Option<String>) -> Self {
+        Self { graphql_endpoint, token }
+    }
+
+    // All queries return the user's clients, `null`s are skipped.
+    fn send_query<Arguments: 'static, ResponseData: 'static, Client: 'static>(
+        &self, 
+        args: Arguments, 
+        build: fn(&Arguments) -> cynic::Operation<'_, ResponseData>,
+        clients: fn(ResponseData) -> Option<Vec<Option<Client>>>,
+    ) -> BackendFuture<Vec<Client>> {
+        let graphql_endpoint = self.graphql_endpoint.clone();
+        let token = self.token.clone();
+        Box::pin(async move {
+            let response_data = graphql::send_operation(&graphql_endpoint, token.as_deref(), build(&args)).await?;
+            Ok(
+                clients(response_data)
+                    .expect("get clients")
+                    .into_iter()
+                    .filter_map(identity)
+                    .collect()
+            )
+        })
+    }
+
+    fn send_mutation<Arguments: 'static, ResponseData: 'static>(
+        &self, 
+        args: Arguments, 
+        build: fn(&Arguments) -> cynic::Operation<'_, ResponseData>,
+    ) -> BackendFuture<()> {
+        let graphql_endpoint = self.graphql_endpoint.clone();
+        let token = self.token.clone();
+        Box::pin(async move {
+            graphql::send_mutation(&graphql_endpoint, token.as_deref(), build(&args)).await
+        })
+    }
+}
+
+impl Backend for GraphQLBackend {
+    // ------ Queries ------
+
+    fn clients_with_projects(
+        &self, 
+        args: clients_with_projects::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects::Client>> {
+        self.send_query(args, |args| clients_with_projects::Query::build(args), |query| query.query_client)
+    }
+
+    fn clients_with_projects_with_time_entries(
+        &self, 
+        args: clients_with_projects_with_time_entries::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects_with_time_entries::Client>> {
+        self.send_query(args, |args| clients_with_projects_with_time_entries::Query::build(args), |query| query.query_client)
+    }
+
+    fn clients_with_projects_with_time_entries_in_range(
+        &self, 
+        args: clients_with_projects_with_time_entries_in_range::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects_with_time_entries_in_range::Client>> {
+        self.send_query(args, |args| clients_with_projects_with_time_entries_in_range::Query::build(args), |query| query.query_client)
+    }
+
+    fn clients_with_time_blocks_and_time_entries(
+        &self, 
+        args: clients_with_time_blocks_and_time_entries::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_time_blocks_and_time_entries::Client>> {
+        self.send_query(args, |args| clients_with_time_blocks_and_time_entries::Query::build(args), |query| query.query_client)
+    }
+
+    // ------ Client ------
+
+    fn add_client(&self, args: client::add::AddClientArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| client::add::Mutation::build(args))
+    }
+
+    fn rename_client(&self, args: client::rename::RenameClientArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| client::rename::Mutation::build(args))
+    }
+
+    fn delete_client(&self, args: client::delete::DeleteClientArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| client::delete::Mutation::build(args))
+    }
+
+    // ------ Project ------
+
+    fn add_project(&self, args: project::add::AddProjectArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| project::add::Mutation::build(args))
+    }
+
+    fn rename_project(&self, args: project::rename::RenameProjectArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| project::rename::Mutation::build(args))
+    }
+
+    fn delete_project(&self, args: project::delete::DeleteProjectArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| project::delete::Mutation::build(args))
+    }
+
+    // ------ TimeEntry ------
+
+    fn add_time_entry(&self, args: time_entry::add::AddTimeEntryArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_entry::add::Mutation::build(args))
+    }
+
+    fn add_time_entries(&self, args: time_entry::add_many::AddTimeEntriesArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_entry::add_many::Mutation::build(args))
+    }
+
+    fn rename_time_entry(&self, args: time_entry::rename::RenameTimeEntryArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_entry::rename::Mutation::build(args))
+    }
+
+    fn delete_time_entry(&self, args: time_entry::delete::DeleteTimeEntryArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_entry::delete::Mutation::build(args))
+    }
+
+    fn set_time_entry_times(&self, args: time_entry::set_times::SetTimeEntryTimesArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_entry::set_times::Mutation::build(args))
+    }
+
+    fn set_time_entry_project(&self, args: time_entry::set_project::SetTimeEntryProjectArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_entry::set_project::Mutation::build(args))
+    }
+
+    // ------ TimeBlock ------
+
+    fn add_time_block(&self, args: time_block::add::AddTimeBlockArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_block::add::Mutation::build(args))
+    }
+
+    fn rename_time_block(&self, args: time_block::rename::RenameTimeBlockArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_block::rename::Mutation::build(args))
+    }
+
+    fn delete_time_block(&self, args: time_block::delete::DeleteTimeBlockArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_block::delete::Mutation::build(args))
+    }
+
+    fn set_time_block_duration(&self, args: time_block::set_duration::SetTimeBlockDurationArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_block::set_duration::Mutation::build(args))
+    }
+
+    fn set_time_block_status(&self, args: time_block::set_status::SetTimeBlockStatusArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| time_block::set_status::Mutation::build(args))
+    }
+
+    // ------ Invoice ------
+
+    fn add_invoice(&self, args: invoice::add::AddInvoiceArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| invoice::add::Mutation::build(args))
+    }
+
+    fn set_invoice_custom_id(&self, args: invoice::set_custom_id::SetInvoiceCustomIdArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| invoice::set_custom_id::Mutation::build(args))
+    }
+
+    fn set_invoice_url(&self, args: invoice::set_url::SetInvoiceUrlArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| invoice::set_url::Mutation::build(args))
+    }
+
+    fn delete_invoice(&self, args: invoice::delete::DeleteInvoiceArguments) -> BackendFuture<()> {
+        self.send_mutation(args, |args| invoice::delete::Mutation::build(args))
+    }
+}
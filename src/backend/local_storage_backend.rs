@@ -0,0 +1,565 @@
+use seed::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use std::collections::BTreeMap;
+
+use crate::graphql::{
+    self,
+    types::DateTime,
+    mutations::{client, invoice, project, time_block, time_entry},
+    queries::{
+        clients_with_projects, 
+        clients_with_projects_with_time_entries, 
+        clients_with_projects_with_time_entries_in_range,
+        clients_with_time_blocks_and_time_entries,
+    },
+};
+use super::{Backend, BackendFuture};
+
+const STORAGE_KEY: &str = "time_tracker-local_storage_backend";
+
+// Keeps all data in the browser - for offline-only use and demos.
+pub struct LocalStorageBackend;
+
+// ------ Data ------
+
+#[derive(Serialize, Deserialize, Default)]
+struct Data {
+    clients: BTreeMap<String, Client>,
+    projects: BTreeMap<String, Project>,
+    time_entries: BTreeMap<String, TimeEntry>,
+    time_blocks: BTreeMap<String, TimeBlock>,
+    invoices: BTreeMap<String, Invoice>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Client {
+    user: String,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Project {
+    client: String,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TimeEntry {
+    project: String,
+    name: String,
+    started: String,
+    stopped: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TimeBlock {
+    client: String,
+    name: String,
+    status: TimeBlockStatus,
+    duration: i32,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone)]
+enum TimeBlockStatus {
+    NonBillable,
+    Unpaid,
+    Paid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Invoice {
+    time_block: String,
+    custom_id: Option<String>,
+    url: Option<String>,
+}
+
+impl Data {
+    fn load() -> graphql::Result<Self> {
+        match LocalStorage::get(STORAGE_KEY) {
+            Ok(data) => Ok(data),
+            Err(WebStorageError::KeyNotFoundError) => Ok(Self::default()),
+            Err(error) => Err(graphql::GraphQLError::LocalStorageError(error)),
+        }
+    }
+
+    fn save(&self) -> graphql::Result<()> {
+        LocalStorage::insert(STORAGE_KEY, self).map_err(graphql::GraphQLError::LocalStorageError)
+    }
+
+    fn user_clients<'a>(&'a self, user: &'a str) -> impl Iterator<Item = (&'a String, &'a Client)> {
+        self.clients.iter().filter(move |(_, client)| client.user == user)
+    }
+
+    fn client_projects<'a>(&'a self, client_id: &'a str) -> impl Iterator<Item = (&'a String, &'a Project)> {
+        self.projects.iter().filter(move |(_, project)| project.client == client_id)
+    }
+
+    fn project_time_entries<'a>(&'a self, project_id: &'a str) -> impl Iterator<Item = (&'a String, &'a TimeEntry)> {
+        self.time_entries.iter().filter(move |(_, time_entry)| time_entry.project == project_id)
+    }
+
+    fn client_time_blocks<'a>(&'a self, client_id: &'a str) -> impl Iterator<Item = (&'a String, &'a TimeBlock)> {
+        self.time_blocks.iter().filter(move |(_, time_block)| time_block.client == client_id)
+    }
+
+    fn time_block_invoice<'a>(&'a self, time_block_id: &'a str) -> Option<(&'a String, &'a Invoice)> {
+        self.invoices.iter().find(|(_, invoice)| invoice.time_block == time_block_id)
+    }
+
+    // Removed entities take their children with them so nothing is left unreachable in the storage.
+
+    fn remove_client(&mut self, client_id: &str) {
+        self.clients.remove(client_id);
+        let project_ids: Vec<_> = self.client_projects(client_id).map(|(id, _)| id.clone()).collect();
+        for project_id in project_ids {
+            self.remove_project(&project_id);
+        }
+        let time_block_ids: Vec<_> = self.client_time_blocks(client_id).map(|(id, _)| id.clone()).collect();
+        for time_block_id in time_block_ids {
+            self.remove_time_block(&time_block_id);
+        }
+    }
+
+    fn remove_project(&mut self, project_id: &str) {
+        self.projects.remove(project_id);
+        self.time_entries.retain(|_, time_entry| time_entry.project != project_id);
+    }
+
+    fn remove_time_block(&mut self, time_block_id: &str) {
+        self.time_blocks.remove(time_block_id);
+        self.invoices.retain(|_, invoice| invoice.time_block != time_block_id);
+    }
+}
+
+// Loads data, lets `change` modify them and saves them.
+fn update_data(change: impl FnOnce(&mut Data)) -> BackendFuture<()> {
+    let result = Data::load().and_then(|mut data| {
+        change(&mut data);
+        data.save()
+    });
+    Box::pin(async { result })
+}
+
+fn query<T: 'static>(read: impl FnOnce(&Data) -> T) -> BackendFuture<T> {
+    let result = Data::load().map(|data| read(&data));
+    Box::pin(async { result })
+}
+
+impl Backend for LocalStorageBackend {
+    // ------ Queries ------
+
+    fn clients_with_projects(
+        &self, 
+        args: clients_with_projects::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects::Client>> {
+        use clients_with_projects as query_mod;
+
+        query(move |data| data.user_clients(&args.user).map(|(client_id, client)| query_mod::Client {
+            id: client_id.clone(),
+            name: client.name.clone(),
+            projects: data.client_projects(client_id).map(|(project_id, project)| query_mod::Project {
+                id: project_id.clone(),
+                name: project.name.clone(),
+            }).collect(),
+        }).collect())
+    }
+
+    fn clients_with_projects_with_time_entries(
+        &self, 
+        args: clients_with_projects_with_time_entries::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects_with_time_entries::Client>> {
+        use clients_with_projects_with_time_entries as query_mod;
+
+        query(move |data| data.user_clients(&args.user).map(|(client_id, client)| query_mod::Client {
+            id: client_id.clone(),
+            name: client.name.clone(),
+            projects: data.client_projects(client_id).map(|(project_id, project)| query_mod::Project {
+                id: project_id.clone(),
+                name: project.name.clone(),
+                time_entries: data.project_time_entries(project_id).map(|(time_entry_id, time_entry)| query_mod::TimeEntry {
+                    id: time_entry_id.clone(),
+                    name: time_entry.name.clone(),
+                    started: DateTime(time_entry.started.clone()),
+                    stopped: time_entry.stopped.clone().map(DateTime),
+                }).collect(),
+            }).collect(),
+        }).collect())
+    }
+
+    fn clients_with_projects_with_time_entries_in_range(
+        &self, 
+        args: clients_with_projects_with_time_entries_in_range::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_projects_with_time_entries_in_range::Client>> {
+        use clients_with_projects_with_time_entries_in_range as query_mod;
+
+        let (started_from, started_to) = (args.started_from, args.started_to);
+        let in_range = move |time_entry: &TimeEntry| {
+            chrono::DateTime::parse_from_rfc3339(&time_entry.started)
+                .map(|started| started.with_timezone(&chrono::Local))
+                .map(|started| started >= started_from && started < started_to)
+                .unwrap_or_default()
+        };
+        query(move |data| data.user_clients(&args.user).map(|(client_id, client)| query_mod::Client {
+            id: client_id.clone(),
+            name: client.name.clone(),
+            projects: data.client_projects(client_id).map(|(project_id, project)| query_mod::Project {
+                id: project_id.clone(),
+                name: project.name.clone(),
+                time_entries: data
+                    .project_time_entries(project_id)
+                    .filter(|(_, time_entry)| in_range(time_entry))
+                    .map(|(time_entry_id, time_entry)| query_mod::TimeEntry {
+                        id: time_entry_id.clone(),
+                        name: time_entry.name.clone(),
+                        started: DateTime(time_entry.started.clone()),
+                        stopped: time_entry.stopped.clone().map(DateTime),
+                    }).collect(),
+            }).collect(),
+        }).collect())
+    }
+
+    fn clients_with_time_blocks_and_time_entries(
+        &self, 
+        args: clients_with_time_blocks_and_time_entries::ClientsArguments
+    ) -> BackendFuture<Vec<clients_with_time_blocks_and_time_entries::Client>> {
+        use clients_with_time_blocks_and_time_entries as query_mod;
+
+        query(move |data| data.user_clients(&args.user).map(|(client_id, client)| query_mod::Client {
+            id: client_id.clone(),
+            name: client.name.clone(),
+            time_blocks: data.client_time_blocks(client_id).map(|(time_block_id, time_block)| query_mod::TimeBlock {
+                id: time_block_id.clone(),
+                name: time_block.name.clone(),
+                status: match time_block.status {
+                    TimeBlockStatus::NonBillable => query_mod::TimeBlockStatus::NonBillable,
+                    TimeBlockStatus::Unpaid => query_mod::TimeBlockStatus::Unpaid,
+                    TimeBlockStatus::Paid => query_mod::TimeBlockStatus::Paid,
+                },
+                duration: time_block.duration,
+                invoice: data.time_block_invoice(time_block_id).map(|(invoice_id, invoice)| query_mod::Invoice {
+                    id: invoice_id.clone(),
+                    custom_id: invoice.custom_id.clone(),
+                    url: invoice.url.clone(),
+                }),
+            }).collect(),
+            projects: data.client_projects(client_id).map(|(project_id, _)| query_mod::Project {
+                time_entries: data.project_time_entries(project_id).map(|(_, time_entry)| query_mod::TimeEntry {
+                    started: DateTime(time_entry.started.clone()),
+                    stopped: time_entry.stopped.clone().map(DateTime),
+                }).collect(),
+            }).collect(),
+        }).collect())
+    }
+
+    // ------ Client ------
+
+    fn add_client(&self, args: client::add::AddClientArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            data.clients.insert(args.id, Client { user: args.user, name: String::new() });
+        })
+    }
+
+    fn rename_client(&self, args: client::rename::RenameClientArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(client) = data.clients.get_mut(&args.id) {
+                client.name = args.name;
+            }
+        })
+    }
+
+    fn delete_client(&self, args: client::delete::DeleteClientArguments) -> BackendFuture<()> {
+        update_data(|data| data.remove_client(&args.id))
+    }
+
+    // ------ Project ------
+
+    fn add_project(&self, args: project::add::AddProjectArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            data.projects.insert(args.id, Project { client: args.client, name: String::new() });
+        })
+    }
+
+    fn rename_project(&self, args: project::rename::RenameProjectArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(project) = data.projects.get_mut(&args.id) {
+                project.name = args.name;
+            }
+        })
+    }
+
+    fn delete_project(&self, args: project::delete::DeleteProjectArguments) -> BackendFuture<()> {
+        update_data(|data| data.remove_project(&args.id))
+    }
+
+    // ------ TimeEntry ------
+
+    fn add_time_entry(&self, args: time_entry::add::AddTimeEntryArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            data.time_entries.insert(args.id, TimeEntry { 
+                project: args.project, 
+                name: args.name, 
+                started: args.started.to_rfc3339(), 
+                stopped: args.stopped.map(|stopped| stopped.to_rfc3339()),
+            });
+        })
+    }
+
+    fn add_time_entries(&self, args: time_entry::add_many::AddTimeEntriesArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            for time_entry in args.time_entries {
+                data.time_entries.insert(time_entry.id, TimeEntry { 
+                    project: time_entry.project, 
+                    name: time_entry.name, 
+                    started: time_entry.started.to_rfc3339(), 
+                    stopped: time_entry.stopped.map(|stopped| stopped.to_rfc3339()),
+                });
+            }
+        })
+    }
+
+    fn rename_time_entry(&self, args: time_entry::rename::RenameTimeEntryArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(time_entry) = data.time_entries.get_mut(&args.id) {
+                time_entry.name = args.name;
+            }
+        })
+    }
+
+    fn delete_time_entry(&self, args: time_entry::delete::DeleteTimeEntryArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            data.time_entries.remove(&args.id);
+        })
+    }
+
+    fn set_time_entry_times(&self, args: time_entry::set_times::SetTimeEntryTimesArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(time_entry) = data.time_entries.get_mut(&args.id) {
+                time_entry.started = args.started.to_rfc3339();
+                time_entry.stopped = args.stopped.map(|stopped| stopped.to_rfc3339());
+            }
+        })
+    }
+
+    fn set_time_entry_project(&self, args: time_entry::set_project::SetTimeEntryProjectArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(time_entry) = data.time_entries.get_mut(&args.id) {
+                time_entry.project = args.project;
+            }
+        })
+    }
+
+    // ------ TimeBlock ------
+
+    fn add_time_block(&self, args: time_block::add::AddTimeBlockArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            data.time_blocks.insert(args.id, TimeBlock { 
+                client: args.client, 
+                name: String::new(), 
+                status: TimeBlockStatus::Unpaid, 
+                duration: args.duration.num_seconds() as i32,
+            });
+        })
+    }
+
+    fn rename_time_block(&self, args: time_block::rename::RenameTimeBlockArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(time_block) = data.time_blocks.get_mut(&args.id) {
+                time_block.name = args.name;
+            }
+        })
+    }
+
+    fn delete_time_block(&self, args: time_block::delete::DeleteTimeBlockArguments) -> BackendFuture<()> {
+        update_data(|data| data.remove_time_block(&args.id))
+    }
+
+    fn set_time_block_duration(&self, args: time_block::set_duration::SetTimeBlockDurationArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(time_block) = data.time_blocks.get_mut(&args.id) {
+                time_block.duration = args.duration;
+            }
+        })
+    }
+
+    fn set_time_block_status(&self, args: time_block::set_status::SetTimeBlockStatusArguments) -> BackendFuture<()> {
+        use time_block::set_status::TimeBlockStatus as MutationTimeBlockStatus;
+
+        update_data(|data| {
+            if let Some(time_block) = data.time_blocks.get_mut(&args.id) {
+                time_block.status = match args.status {
+                    MutationTimeBlockStatus::NonBillable => TimeBlockStatus::NonBillable,
+                    MutationTimeBlockStatus::Unpaid => TimeBlockStatus::Unpaid,
+                    MutationTimeBlockStatus::Paid => TimeBlockStatus::Paid,
+                };
+            }
+        })
+    }
+
+    // ------ Invoice ------
+
+    fn add_invoice(&self, args: invoice::add::AddInvoiceArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            data.invoices.insert(args.id, Invoice { 
+                time_block: args.time_block, 
+                custom_id: None, 
+                url: None,
+            });
+        })
+    }
+
+    fn set_invoice_custom_id(&self, args: invoice::set_custom_id::SetInvoiceCustomIdArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(invoice) = data.invoices.get_mut(&args.id) {
+                invoice.custom_id = Some(args.custom_id);
+            }
+        })
+    }
+
+    fn set_invoice_url(&self, args: invoice::set_url::SetInvoiceUrlArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            if let Some(invoice) = data.invoices.get_mut(&args.id) {
+                invoice.url = Some(args.url);
+            }
+        })
+    }
+
+    fn delete_invoice(&self, args: invoice::delete::DeleteInvoiceArguments) -> BackendFuture<()> {
+        update_data(|data| {
+            data.invoices.remove(&args.id);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn data() -> Data {
+        let mut data = Data::default();
+        for (client_id, user) in &[("c1", "u1"), ("c2", "u1")] {
+            data.clients.insert(client_id.to_string(), Client { user: user.to_string(), name: client_id.to_string() });
+
+            let project_id = format!("{}-p", client_id);
+            data.projects.insert(project_id.clone(), Project { client: client_id.to_string(), name: project_id.clone() });
+            data.time_entries.insert(format!("{}-e", client_id), TimeEntry {
+                project: project_id,
+                name: String::new(),
+                started: "2021-01-01T08:00:00+00:00".to_owned(),
+                stopped: None,
+            });
+
+            let time_block_id = format!("{}-b", client_id);
+            data.time_blocks.insert(time_block_id.clone(), TimeBlock {
+                client: client_id.to_string(),
+                name: time_block_id.clone(),
+                status: TimeBlockStatus::Unpaid,
+                duration: 3600,
+            });
+            data.invoices.insert(format!("{}-i", client_id), Invoice { time_block: time_block_id, custom_id: None, url: None });
+        }
+        data
+    }
+
+    fn keys<T>(map: &BTreeMap<String, T>) -> Vec<&str> {
+        map.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn remove_client_removes_its_children() {
+        let mut data = data();
+        data.remove_client("c1");
+
+        assert_eq!(keys(&data.clients), ["c2"]);
+        assert_eq!(keys(&data.projects), ["c2-p"]);
+        assert_eq!(keys(&data.time_entries), ["c2-e"]);
+        assert_eq!(keys(&data.time_blocks), ["c2-b"]);
+        assert_eq!(keys(&data.invoices), ["c2-i"]);
+    }
+
+    #[test]
+    fn remove_project_removes_only_its_time_entries() {
+        let mut data = data();
+        data.remove_project("c1-p");
+
+        assert_eq!(keys(&data.clients), ["c1", "c2"]);
+        assert_eq!(keys(&data.projects), ["c2-p"]);
+        assert_eq!(keys(&data.time_entries), ["c2-e"]);
+        assert_eq!(keys(&data.time_blocks), ["c1-b", "c2-b"]);
+    }
+
+    #[test]
+    fn remove_time_block_removes_its_invoice() {
+        let mut data = data();
+        data.remove_time_block("c2-b");
+
+        assert_eq!(keys(&data.time_blocks), ["c1-b"]);
+        assert_eq!(keys(&data.invoices), ["c1-i"]);
+        assert_eq!(keys(&data.projects), ["c1-p", "c2-p"]);
+    }
+
+    // ------ Backend ------
+
+    fn clear_storage() {
+        LocalStorage::remove(STORAGE_KEY).expect("clear storage");
+    }
+
+    fn add_client(id: &str) -> BackendFuture<()> {
+        LocalStorageBackend.add_client(client::add::AddClientArguments { id: id.to_owned(), user: "u1".to_owned() })
+    }
+
+    fn add_project(id: &str, client: &str) -> BackendFuture<()> {
+        LocalStorageBackend.add_project(project::add::AddProjectArguments { id: id.to_owned(), client: client.to_owned() })
+    }
+
+    fn add_time_entry(id: &str, project: &str) -> BackendFuture<()> {
+        LocalStorageBackend.add_time_entry(time_entry::add::AddTimeEntryArguments {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            project: project.to_owned(),
+            started: chrono::Local::now(),
+            stopped: None,
+        })
+    }
+
+    #[wasm_bindgen_test]
+    async fn backend_adds_and_queries_clients() {
+        clear_storage();
+        add_client("c1").await.unwrap();
+        add_project("c1-p", "c1").await.unwrap();
+        add_time_entry("c1-e", "c1-p").await.unwrap();
+
+        let args = clients_with_projects_with_time_entries::ClientsArguments { user: "u1".to_owned() };
+        let clients = LocalStorageBackend.clients_with_projects_with_time_entries(args).await.unwrap();
+
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].id, "c1");
+        assert_eq!(clients[0].projects.len(), 1);
+        assert_eq!(clients[0].projects[0].id, "c1-p");
+        assert_eq!(clients[0].projects[0].time_entries.len(), 1);
+        assert_eq!(clients[0].projects[0].time_entries[0].name, "c1-e");
+
+        let args = clients_with_projects::ClientsArguments { user: "u2".to_owned() };
+        assert!(LocalStorageBackend.clients_with_projects(args).await.unwrap().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn backend_delete_client_removes_its_children() {
+        clear_storage();
+        for client_id in &["c1", "c2"] {
+            add_client(client_id).await.unwrap();
+            add_project(&format!("{}-p", client_id), client_id).await.unwrap();
+            add_time_entry(&format!("{}-e", client_id), &format!("{}-p", client_id)).await.unwrap();
+        }
+
+        LocalStorageBackend.delete_client(client::delete::DeleteClientArguments { id: "c1".to_owned() }).await.unwrap();
+
+        let data = Data::load().unwrap();
+        assert_eq!(keys(&data.clients), ["c2"]);
+        assert_eq!(keys(&data.projects), ["c2-p"]);
+        assert_eq!(keys(&data.time_entries), ["c2-e"]);
+    }
+}
@@ -0,0 +1,169 @@
+use seed::{prelude::*, *};
+
+use std::{borrow::Cow, mem};
+
+// ------ ------
+//     Write
+// ------ ------
+
+// Spreadsheet apps would evaluate fields starting with these characters as formulas.
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+const FORMULA_ESCAPE: char = '\'';
+
+// RFC 4180 - fields are quoted only when necessary and lines end with CRLF.
+// Fields that look like formulas are prefixed with `'`; `read` removes the prefix again.
+pub fn write(rows: impl IntoIterator<Item = Vec<String>>) -> String {
+    let mut csv = String::new();
+    for row in rows {
+        let fields = row.iter().map(|field| escape(field)).collect::<Vec<_>>();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+fn escape(field: &str) -> Cow<str> {
+    let field = if looks_like_formula(field) {
+        Cow::Owned(format!("{}{}", FORMULA_ESCAPE, field))
+    } else {
+        Cow::Borrowed(field)
+    };
+    if field.contains(|character| matches!(character, ',' | '"' | '\r' | '\n')) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        field
+    }
+}
+
+// ------ ------
+//     Read
+// ------ ------
+
+// RFC 4180, but any line ending is accepted and empty lines are skipped.
+pub fn read(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+
+    let mut characters = text.chars().peekable();
+    while let Some(character) = characters.next() {
+        match (in_quotes, character) {
+            (true, '"') if characters.peek() == Some(&'"') => {
+                characters.next();
+                field.push('"');
+            },
+            (true, '"') => in_quotes = false,
+            (true, character) => field.push(character),
+            (false, '"') if field.is_empty() => in_quotes = true,
+            (false, ',') => row.push(unescape(mem::take(&mut field))),
+            (false, '\r') if characters.peek() == Some(&'\n') => (),
+            (false, '\r') | (false, '\n') => {
+                row.push(unescape(mem::take(&mut field)));
+                push_row(&mut rows, mem::take(&mut row));
+            },
+            (false, character) => field.push(character),
+        }
+    }
+    if in_quotes {
+        Err("The file ends inside a quoted field.".to_owned())?
+    }
+    row.push(unescape(field));
+    push_row(&mut rows, row);
+    Ok(rows)
+}
+
+fn unescape(field: String) -> String {
+    match field.strip_prefix(FORMULA_ESCAPE) {
+        Some(unescaped) if looks_like_formula(&field) => unescaped.to_owned(),
+        _ => field,
+    }
+}
+
+// Already escaped formulas are escaped again so `read` returns exactly what has been written.
+fn looks_like_formula(field: &str) -> bool {
+    field.trim_start_matches(FORMULA_ESCAPE).starts_with(&FORMULA_PREFIXES[..])
+}
+
+fn push_row(rows: &mut Vec<Vec<String>>, row: Vec<String>) {
+    if not(row.len() == 1 && row[0].is_empty()) {
+        rows.push(row);
+    }
+}
+
+// ------ ------
+//   Download
+// ------ ------
+
+// Lets the browser revoke the download URL only after the download has started.
+const REVOKE_URL_DELAY_MS: i32 = 1_000;
+
+// Lets the browser save `csv` as a file without a round trip to the server.
+pub fn download(file_name: &str, csv: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(csv));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_("text/csv;charset=utf-8");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let link = document()
+        .create_element("a")?
+        .unchecked_into::<web_sys::HtmlAnchorElement>();
+    link.set_href(&url);
+    link.set_download(file_name);
+    link.click();
+
+    let revoke_url = Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    window()
+        .set_timeout_with_callback_and_timeout_and_arguments_0(revoke_url.unchecked_ref(), REVOKE_URL_DELAY_MS)
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn write_quotes_only_when_necessary() {
+        let csv = write(vec![
+            row(&["name", "note"]),
+            row(&["plain", "a, b"]),
+            row(&["say \"hi\"", "line\r\nbreak"]),
+        ]);
+        assert_eq!(csv, "name,note\r\nplain,\"a, b\"\r\n\"say \"\"hi\"\"\",\"line\r\nbreak\"\r\n");
+    }
+
+    #[test]
+    fn read_parses_what_write_writes() {
+        let rows = vec![row(&["a", ""]), row(&["\"quoted\"", "x,y"]), row(&["multi\r\nline", "z"])];
+        assert_eq!(read(&write(rows.clone())), Ok(rows));
+    }
+
+    #[test]
+    fn read_accepts_bom_line_endings_and_empty_lines() {
+        let text = "\u{feff}a,b\n\r\n1,\"2\"\"3\"\r4,\"5\r\n6\"";
+        assert_eq!(read(text), Ok(vec![row(&["a", "b"]), row(&["1", "2\"3"]), row(&["4", "5\r\n6"])]));
+    }
+
+    #[test]
+    fn write_and_read_escape_formulas() {
+        let rows = vec![row(&["=SUM(A1:A2)", "+1", "-1", "@cmd", "\tcmd", "\r=1", "a=b", "'=quoted", "''-1", "'text"])];
+        let csv = write(rows.clone());
+
+        assert_eq!(csv, "'=SUM(A1:A2),'+1,'-1,'@cmd,'\tcmd,\"'\r=1\",a=b,''=quoted,'''-1,'text\r\n");
+        assert_eq!(read(&csv), Ok(rows));
+    }
+
+    #[test]
+    fn read_rejects_unterminated_quotes() {
+        assert!(read("a,\"b\r\nc").is_err());
+    }
+}
@@ -1,16 +1,23 @@
-use seed::{prelude::*};
+use seed::{prelude::*, *};
 
 use cynic;
 
+use std::fmt;
+
 pub type Result<T> = std::result::Result<T, GraphQLError>;
 
 pub async fn send_operation<'a, ResponseData: 'a>(
+    graphql_endpoint: &str,
+    token: Option<&str>,
     operation: cynic::Operation<'a, ResponseData>
 ) -> Result<ResponseData> {
+    let mut request = Request::new(graphql_endpoint).method(Method::Post);
+    if let Some(token) = token {
+        request = request.header(Header::bearer(token));
+    }
+
     let graphql_response =
-        // @TODO: Move url to a config file.
-        Request::new("https://time-tracker.eu-central-1.aws.cloud.dgraph.io/graphql")
-            .method(Method::Post)
+        request
             .json(&operation)?
             .fetch()
             .await?
@@ -25,13 +32,96 @@ pub async fn send_operation<'a, ResponseData: 'a>(
     Ok(response_data.data.expect("response data"))
 }
 
+// Mutations are queued when the app is offline or older mutations are still waiting in the queue.
+pub async fn send_mutation<'a, ResponseData: 'a>(
+    graphql_endpoint: &str,
+    token: Option<&str>,
+    operation: cynic::Operation<'a, ResponseData>
+) -> Result<()> {
+    let queued_operation = serde_json::to_value(&operation).expect("serialize operation");
+
+    if not(queue::is_empty()) || not(window().navigator().on_line()) {
+        queue::push(queued_operation);
+        Err(GraphQLError::Queued)?
+    }
+    match send_operation(graphql_endpoint, token, operation).await {
+        Err(graphql_error) if graphql_error.is_network_error() => {
+            queue::push(queued_operation);
+            Err(GraphQLError::Queued)
+        },
+        result => result.map(drop),
+    }
+}
+
+pub mod queue;
+
 // ------ Error ------
 
 #[derive(Debug)]
 pub enum GraphQLError {
     FetchError(FetchError),
     ResponseErrors(Vec<cynic::GraphQLError>),
-    DecodeError(cynic::DecodeError)
+    DecodeError(cynic::DecodeError),
+    LocalStorageError(WebStorageError),
+    Queued,
+}
+
+impl GraphQLError {
+    pub fn is_network_error(&self) -> bool {
+        match self {
+            Self::FetchError(FetchError::NetworkError(_)) => true,
+            Self::FetchError(FetchError::StatusError(status)) => status.code >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FetchError(fetch_error) => match fetch_error {
+                FetchError::NetworkError(_) => {
+                    write!(f, "Cannot connect to the server. Please check your internet connection.")
+                },
+                FetchError::StatusError(status) => {
+                    write!(f, "The server responded with status {} {}.", status.code, status.text)
+                },
+                FetchError::SerdeError(serde_error) => {
+                    write!(f, "The server response cannot be processed: {}", serde_error)
+                },
+                fetch_error => write!(f, "The request failed: {:?}", fetch_error),
+            },
+            Self::ResponseErrors(response_errors) => {
+                for (index, response_error) in response_errors.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " ")?;
+                    }
+                    if let Some(path) = &response_error.path {
+                        let path = path
+                            .iter()
+                            .map(|segment| match segment {
+                                cynic::GraphQLErrorPathSegment::Field(field) => field.clone(),
+                                cynic::GraphQLErrorPathSegment::Index(index) => index.to_string(),
+                            })
+                            .collect::<Vec<_>>()
+                            .join(".");
+                        write!(f, "[{}] ", path)?;
+                    }
+                    write!(f, "{}", response_error.message)?;
+                }
+                Ok(())
+            },
+            Self::DecodeError(decode_error) => {
+                write!(f, "The server sent unexpected data: {:?}", decode_error)
+            },
+            Self::LocalStorageError(web_storage_error) => {
+                write!(f, "Data cannot be saved in the browser: {:?}", web_storage_error)
+            },
+            Self::Queued => {
+                write!(f, "The change will be saved when the connection is restored.")
+            },
+        }
+    }
 }
 
 impl From<FetchError> for GraphQLError {
@@ -64,11 +154,11 @@ pub mod queries {
         query_module = "query_dsl",
     )]
     pub mod clients_with_projects {
-        use crate::graphql::query_dsl;
+        use crate::graphql::{query_dsl, types::*};
 
         ///```graphql
         ///{
-        ///    queryClient {
+        ///    queryClient(filter: {user: {eq: "[user id]"}}) {
         ///        id
         ///        name
         ///        projects {
@@ -79,11 +169,36 @@ pub mod queries {
         ///}
         ///```
         #[derive(cynic::QueryFragment, Debug)]
-        #[cynic(graphql_type = "Query")]
+        #[cynic(
+            graphql_type = "Query",
+            argument_struct = "ClientsArguments",
+        )]
         pub struct Query {
+            #[arguments(filter = ClientFilter {
+                user: Some(StringHashFilter {
+                    eq: Some(args.user.clone()),
+                })
+            })]
             pub query_client: Option<Vec<Option<Client>>>,
         }
 
+        #[derive(cynic::FragmentArguments, Debug)]
+        pub struct ClientsArguments {
+            pub user: String,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "ClientFilter")]
+        pub struct ClientFilter {
+            pub user: Option<StringHashFilter>,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "StringHashFilter")]
+        pub struct StringHashFilter {
+            pub eq: Option<String>,
+        }
+
         #[derive(cynic::QueryFragment, Debug)]
         #[cynic(graphql_type = "Client")]
         pub struct Client {
@@ -109,7 +224,7 @@ pub mod queries {
 
         ///```graphql
         ///{
-        ///    queryClient {
+        ///    queryClient(filter: {user: {eq: "[user id]"}}) {
         ///        id
         ///        name
         ///        projects {
@@ -126,11 +241,36 @@ pub mod queries {
         ///}
         ///```
         #[derive(cynic::QueryFragment, Debug)]
-        #[cynic(graphql_type = "Query")]
+        #[cynic(
+            graphql_type = "Query",
+            argument_struct = "ClientsArguments",
+        )]
         pub struct Query {
+            #[arguments(filter = ClientFilter {
+                user: Some(StringHashFilter {
+                    eq: Some(args.user.clone()),
+                })
+            })]
             pub query_client: Option<Vec<Option<Client>>>,
         }
 
+        #[derive(cynic::FragmentArguments, Debug)]
+        pub struct ClientsArguments {
+            pub user: String,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "ClientFilter")]
+        pub struct ClientFilter {
+            pub user: Option<StringHashFilter>,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "StringHashFilter")]
+        pub struct StringHashFilter {
+            pub eq: Option<String>,
+        }
+
         #[derive(cynic::QueryFragment, Debug)]
         #[cynic(graphql_type = "Client")]
         pub struct Client {
@@ -157,6 +297,127 @@ pub mod queries {
         }
     }
 
+    #[cynic::query_module(
+        schema_path = "schema.graphql",
+        query_module = "query_dsl",
+    )]
+    pub mod clients_with_projects_with_time_entries_in_range {
+        use crate::graphql::{query_dsl, types::*};
+
+        ///```graphql
+        ///{
+        ///    queryClient(filter: {user: {eq: "[user id]"}}) {
+        ///        id
+        ///        name
+        ///        projects {
+        ///            id
+        ///            name
+        ///            time_entries(filter: {
+        ///                started: {ge: "2020-01-13T00:00:00+01:00"},
+        ///                and: {started: {lt: "2020-01-20T00:00:00+01:00"}}
+        ///            }) {
+        ///                id
+        ///                name
+        ///                started
+        ///                stopped
+        ///            }
+        ///        }
+        ///    }
+        ///}
+        ///```
+        #[derive(cynic::QueryFragment, Debug)]
+        #[cynic(
+            graphql_type = "Query",
+            argument_struct = "ClientsArguments",
+        )]
+        pub struct Query {
+            #[arguments(filter = ClientFilter {
+                user: Some(StringHashFilter {
+                    eq: Some(args.user.clone()),
+                })
+            })]
+            pub query_client: Option<Vec<Option<Client>>>,
+        }
+
+        // Time entries started in the range `[started_from, started_to)`.
+        #[derive(cynic::FragmentArguments, Debug)]
+        pub struct ClientsArguments {
+            pub user: String,
+            pub started_from: chrono::DateTime<chrono::Local>,
+            pub started_to: chrono::DateTime<chrono::Local>,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "ClientFilter")]
+        pub struct ClientFilter {
+            pub user: Option<StringHashFilter>,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "StringHashFilter")]
+        pub struct StringHashFilter {
+            pub eq: Option<String>,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "TimeEntryFilter")]
+        pub struct TimeEntryFilter {
+            pub started: Option<DateTimeFilter>,
+            pub and: Option<Box<TimeEntryFilter>>,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "DateTimeFilter")]
+        pub struct DateTimeFilter {
+            pub ge: Option<DateTime>,
+            pub lt: Option<DateTime>,
+        }
+
+        #[derive(cynic::QueryFragment, Debug)]
+        #[cynic(
+            graphql_type = "Client",
+            argument_struct = "ClientsArguments",
+        )]
+        pub struct Client {
+            pub id: String,
+            pub name: String,
+            pub projects: Vec<Project>,
+        }
+
+        #[derive(cynic::QueryFragment, Debug)]
+        #[cynic(
+            graphql_type = "Project",
+            argument_struct = "ClientsArguments",
+        )]
+        pub struct Project {
+            pub id: String,
+            pub name: String,
+            #[arguments(filter = TimeEntryFilter {
+                started: Some(DateTimeFilter {
+                    ge: Some(DateTime(args.started_from.to_rfc3339())),
+                    lt: None,
+                }),
+                and: Some(Box::new(TimeEntryFilter {
+                    started: Some(DateTimeFilter {
+                        ge: None,
+                        lt: Some(DateTime(args.started_to.to_rfc3339())),
+                    }),
+                    and: None,
+                })),
+            })]
+            pub time_entries: Vec<TimeEntry>,
+        }
+
+        #[derive(cynic::QueryFragment, Debug)]
+        #[cynic(graphql_type = "TimeEntry")]
+        pub struct TimeEntry {
+            pub id: String,
+            pub name: String,
+            pub started: DateTime,
+            pub stopped: Option<DateTime>,
+        }
+    }
+
     #[cynic::query_module(
         schema_path = "schema.graphql",
         query_module = "query_dsl",
@@ -166,7 +427,7 @@ pub mod queries {
 
         ///```graphql
         ///{
-        ///    queryClient {
+        ///    queryClient(filter: {user: {eq: "[user id]"}}) {
         ///        id
         ///        name
         ///        time_blocks {
@@ -190,11 +451,36 @@ pub mod queries {
         ///}
         ///```
         #[derive(cynic::QueryFragment, Debug)]
-        #[cynic(graphql_type = "Query")]
+        #[cynic(
+            graphql_type = "Query",
+            argument_struct = "ClientsArguments",
+        )]
         pub struct Query {
+            #[arguments(filter = ClientFilter {
+                user: Some(StringHashFilter {
+                    eq: Some(args.user.clone()),
+                })
+            })]
             pub query_client: Option<Vec<Option<Client>>>,
         }
 
+        #[derive(cynic::FragmentArguments, Debug)]
+        pub struct ClientsArguments {
+            pub user: String,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "ClientFilter")]
+        pub struct ClientFilter {
+            pub user: Option<StringHashFilter>,
+        }
+
+        #[derive(cynic::InputObject, Debug)]
+        #[cynic(graphql_type = "StringHashFilter")]
+        pub struct StringHashFilter {
+            pub eq: Option<String>,
+        }
+
         #[derive(cynic::QueryFragment, Debug)]
         #[cynic(graphql_type = "Client")]
         pub struct Client {
@@ -245,7 +531,7 @@ pub mod queries {
     }
 }
 
-mod types {
+pub mod types {
     #[derive(cynic::Scalar, Debug)]
     pub struct DateTime(pub String);
 }
@@ -11,6 +11,7 @@ pub mod add {
     ///       id: "[time_entry id]",
     ///       name: "[time_entry name]",
     ///       started: "2020-01-15T15:53:39Z",
+    ///       stopped: "2020-01-15T16:53:39Z",
     ///       project: { id: "[project id]" },
     ///     }) {
     ///       numUids
@@ -28,6 +29,7 @@ pub mod add {
                 id: args.id.clone(),
                 name: args.name.clone(),
                 started: DateTime(args.started.to_rfc3339()),
+                stopped: args.stopped.map(|stopped| DateTime(stopped.to_rfc3339())),
                 project: ProjectRef {
                     id: Some(args.project.clone())
                 },
@@ -42,6 +44,7 @@ pub mod add {
         pub name: String,
         pub project: String,
         pub started: chrono::DateTime<chrono::Local>,
+        pub stopped: Option<chrono::DateTime<chrono::Local>>,
     }
 
     #[derive(cynic::InputObject, Debug)]
@@ -50,6 +53,87 @@ pub mod add {
         id: String,
         name: String,
         started: DateTime,
+        stopped: Option<DateTime>,
+        project: ProjectRef,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    #[cynic(graphql_type = "ProjectRef")]
+    pub struct ProjectRef {
+        id: Option<String>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(graphql_type = "AddTimeEntryPayload")]
+    pub struct AddTimeEntryPayload {
+        pub num_uids: Option<i32>,
+    }
+}
+
+#[cynic::query_module(
+    schema_path = "schema.graphql",
+    query_module = "query_dsl",
+)]
+pub mod add_many {
+    use crate::graphql::{query_dsl, types::*};
+    
+    ///```graphql
+    /// mutation {
+    ///     addTimeEntry(input: [
+    ///       {
+    ///         id: "[time_entry id]",
+    ///         name: "[time_entry name]",
+    ///         started: "2020-01-15T15:53:39Z",
+    ///         stopped: "2020-01-15T16:53:39Z",
+    ///         project: { id: "[project id]" },
+    ///       },
+    ///       ...
+    ///     ]) {
+    ///       numUids
+    ///     }
+    ///   }
+    ///```
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(
+        graphql_type = "Mutation",
+        argument_struct = "AddTimeEntriesArguments",
+    )]
+    pub struct Mutation {
+        #[arguments(input = args.time_entries.iter().map(|time_entry| {
+            AddTimeEntryInput {
+                id: time_entry.id.clone(),
+                name: time_entry.name.clone(),
+                started: DateTime(time_entry.started.to_rfc3339()),
+                stopped: time_entry.stopped.map(|stopped| DateTime(stopped.to_rfc3339())),
+                project: ProjectRef {
+                    id: Some(time_entry.project.clone())
+                },
+            }
+        }).collect::<Vec<_>>())]
+        pub add_time_entry: Option<AddTimeEntryPayload>,
+    }
+
+    #[derive(cynic::FragmentArguments, Debug)]
+    pub struct AddTimeEntriesArguments {
+        pub time_entries: Vec<NewTimeEntry>,
+    }
+
+    #[derive(Debug)]
+    pub struct NewTimeEntry {
+        pub id: String,
+        pub name: String,
+        pub project: String,
+        pub started: chrono::DateTime<chrono::Local>,
+        pub stopped: Option<chrono::DateTime<chrono::Local>>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    #[cynic(graphql_type = "AddTimeEntryInput")]
+    pub struct AddTimeEntryInput {
+        id: String,
+        name: String,
+        started: DateTime,
+        stopped: Option<DateTime>,
         project: ProjectRef,
     }
 
@@ -275,3 +359,85 @@ pub mod set_times {
 }
 
 
+
+#[cynic::query_module(
+    schema_path = "schema.graphql",
+    query_module = "query_dsl",
+)]
+pub mod set_project {
+    use crate::graphql::{query_dsl, types::*};
+
+    ///```graphql
+    /// mutation {
+    ///     updateTimeEntry(input: {
+    ///       filter: {id: {eq: "[time_entry id]"}}
+    ///       set: {project: { id: "[project id]" }}
+    ///     }) {
+    ///       numUids
+    ///     }
+    ///   }
+    ///```
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(
+        graphql_type = "Mutation",
+        argument_struct = "SetTimeEntryProjectArguments",
+    )]
+    pub struct Mutation {
+        #[arguments(input = UpdateTimeEntryInput {
+            filter: TimeEntryFilter {
+                id: Some(StringHashFilter {
+                    eq: Some(args.id.clone()),
+                }),
+            },
+            set: Some(TimeEntryPatch {
+                project: Some(ProjectRef {
+                    id: Some(args.project.clone()),
+                }),
+            }),
+        })]
+        pub update_time_entry: Option<UpdateTimeEntryPayload>,
+    }
+
+    #[derive(cynic::FragmentArguments, Debug)]
+    pub struct SetTimeEntryProjectArguments {
+        pub id: String,
+        pub project: String,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    #[cynic(graphql_type = "UpdateTimeEntryInput")]
+    pub struct UpdateTimeEntryInput {
+        pub filter: TimeEntryFilter,
+        pub set: Option<TimeEntryPatch>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    #[cynic(graphql_type = "TimeEntryFilter")]
+    pub struct TimeEntryFilter {
+        pub id: Option<StringHashFilter>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    #[cynic(graphql_type = "StringHashFilter")]
+    pub struct StringHashFilter {
+        pub eq: Option<String>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    #[cynic(graphql_type = "TimeEntryPatch")]
+    pub struct TimeEntryPatch {
+        pub project: Option<ProjectRef>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    #[cynic(graphql_type = "ProjectRef")]
+    pub struct ProjectRef {
+        pub id: Option<String>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(graphql_type = "UpdateTimeEntryPayload")]
+    pub struct UpdateTimeEntryPayload {
+        pub num_uids: Option<i32>,
+    }
+}
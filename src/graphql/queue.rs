@@ -0,0 +1,101 @@
+use seed::{prelude::*, *};
+use serde_json::Value;
+
+use super::{GraphQLError, Result};
+
+const STORAGE_KEY: &str = "time_tracker-mutation_queue";
+
+// ------ QueueChanged ------
+
+// Notify it when a mutation has been added to the queue.
+#[derive(Clone)]
+pub struct QueueChanged;
+
+// ------ Storage ------
+
+fn load() -> Vec<Value> {
+    LocalStorage::get(STORAGE_KEY).unwrap_or_default()
+}
+
+fn save(operations: &[Value]) {
+    if let Err(error) = LocalStorage::insert(STORAGE_KEY, &operations) {
+        error!("Cannot save mutation queue!", error);
+    }
+}
+
+pub fn len() -> usize {
+    load().len()
+}
+
+pub fn is_empty() -> bool {
+    load().is_empty()
+}
+
+pub fn push(operation: Value) {
+    let mut operations = load();
+    operations.push(operation);
+    save(&operations);
+}
+
+// Whether a queued mutation refers to the given ID, e.g. adds the entity with the ID.
+pub fn refers_to(id: &str) -> bool {
+    load().iter().any(|operation| contains_string(&operation["variables"], id))
+}
+
+fn contains_string(value: &Value, string: &str) -> bool {
+    match value {
+        Value::String(value) => value == string,
+        Value::Array(values) => values.iter().any(|value| contains_string(value, string)),
+        Value::Object(map) => map.values().any(|value| contains_string(value, string)),
+        _ => false,
+    }
+}
+
+fn remove_first() {
+    let mut operations = load();
+    if not(operations.is_empty()) {
+        operations.remove(0);
+        save(&operations);
+    }
+}
+
+// ------ Replay ------
+
+// Sends queued mutations in order and stops on the first network error to preserve the order. 
+// Mutations rejected by the server are removed from the queue and returned.
+pub async fn replay(
+    graphql_endpoint: String, 
+    token: Option<String>
+) -> std::result::Result<Vec<GraphQLError>, GraphQLError> {
+    let mut rejected = Vec::new();
+    while let Some(operation) = load().into_iter().next() {
+        match send(&graphql_endpoint, token.as_deref(), &operation).await {
+            Ok(()) => (),
+            Err(graphql_error) if graphql_error.is_network_error() => Err(graphql_error)?,
+            Err(graphql_error) => rejected.push(graphql_error),
+        }
+        remove_first();
+    }
+    Ok(rejected)
+}
+
+async fn send(graphql_endpoint: &str, token: Option<&str>, operation: &Value) -> Result<()> {
+    let mut request = Request::new(graphql_endpoint).method(Method::Post);
+    if let Some(token) = token {
+        request = request.header(Header::bearer(token));
+    }
+
+    let graphql_response: cynic::GraphQLResponse<Value> =
+        request
+            .json(operation)?
+            .fetch()
+            .await?
+            .check_status()?
+            .json()
+            .await?;
+
+    if let Some(errors) = graphql_response.errors {
+        Err(errors)?
+    }
+    Ok(())
+}
@@ -4,15 +4,43 @@
 
 use seed::{prelude::*, *};
 use serde::Deserialize;
+use chrono::prelude::*;
+use ulid::Ulid;
+
+use backend::{Backend, GraphQLBackend, LocalStorageBackend};
 
 mod page;
 mod graphql;
+mod backend;
+mod parser;
+mod csv;
 
 const CLIENTS_AND_PROJECTS: &str = "clients_and_projects";
 const TIME_TRACKER: &str = "time_tracker";
 const TIME_BLOCKS: &str = "time_blocks";
+const REPORTS: &str = "reports";
+const TIMESHEET: &str = "timesheet";
+const CALENDAR: &str = "calendar";
+const IMPORT: &str = "import";
 const SETTINGS: &str = "settings";
 
+const APP_CONFIG_PATH: &str = "/app_config.json";
+const APP_CONFIG_OVERRIDE_PATH: &str = "/app_config.local.json";
+
+const TITLE: &str = "Time Tracker";
+const FAVICON_ID: &str = "favicon";
+const FAVICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'%3E%3Ccircle cx='50' cy='50' r='40' fill='none' stroke='%233273dc' stroke-width='16'/%3E%3C/svg%3E";
+const FAVICON_RECORDING: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'%3E%3Ccircle cx='50' cy='50' r='40' fill='none' stroke='%23f14668' stroke-width='16'/%3E%3Ccircle cx='50' cy='50' r='22' fill='%23f14668'/%3E%3C/svg%3E";
+
+// Owner of the data stored by `LocalStorageBackend` when nobody is logged in.
+const LOCAL_USER_ID: &str = "local";
+
+const MIN_REPLAY_DELAY_MS: u32 = 2_000;
+const MAX_REPLAY_DELAY_MS: u32 = 120_000;
+
+// A lower idle threshold would mark the user idle on every timer tick.
+const MIN_IDLE_THRESHOLD_MINUTES: i64 = 1;
+
 // ------ ------
 //     Init
 // ------ ------
@@ -20,7 +48,14 @@ const SETTINGS: &str = "settings";
 fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
     orders
         .subscribe(Msg::UrlChanged)
+        .subscribe(|_: graphql::queue::QueueChanged| Msg::MutationQueueChanged)
+        .subscribe(|RunningTimeEntryChanged(time_entry)| Msg::RunningTimeEntryChanged(time_entry))
+        .stream(streams::window_event(Ev::from("online"), |_| Msg::OnOnline))
+        .stream(streams::window_event(Ev::MouseMove, |_| Msg::OnUserActivity))
+        .stream(streams::window_event(Ev::KeyDown, |_| Msg::OnUserActivity))
+        .stream(streams::document_event(Ev::from("visibilitychange"), |_| Msg::OnVisibilityChange))
         .stream(streams::window_event(Ev::Click, |_| Msg::HideMenu))
+        .perform_cmd(async { Msg::AppConfigFetched(fetch_app_config().await) })
         .perform_cmd(async { 
             Msg::AuthConfigFetched(
                 async { fetch("/auth_config.json").await?.check_status()?.json().await }.await
@@ -31,11 +66,26 @@ fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
         ctx: Context {
             user: None,
             token: None,
+            app_config: None,
+            auth_initialized: false,
         },
         base_url: url.to_base_url(),
-        page: Page::init(url, orders),
+        page: Page::Loading,
         menu_visible: false,
         auth_config: None,
+        mutation_queue: MutationQueue {
+            len: graphql::queue::len(),
+            replaying: false,
+            replay_delay_ms: MIN_REPLAY_DELAY_MS,
+            replay_handle: None,
+        },
+        running_timer: RunningTimer {
+            projects: Vec::new(),
+            time_entry: None,
+            timer_handle: None,
+            last_activity: Local::now(),
+            idle: None,
+        },
     }
 }
 
@@ -49,11 +99,43 @@ struct Model {
     page: Page,
     menu_visible: bool,
     auth_config: Option<AuthConfig>,
+    mutation_queue: MutationQueue,
+    running_timer: RunningTimer,
+}
+
+struct MutationQueue {
+    len: usize,
+    replaying: bool,
+    replay_delay_ms: u32,
+    replay_handle: Option<CmdHandle>,
 }
 
 struct Context {
     user: Option<User>,
     token: Option<String>,
+    app_config: Option<AppConfig>,
+    auth_initialized: bool,
+}
+
+impl Context {
+    fn backend(&self) -> Option<Box<dyn Backend>> {
+        let app_config = self.app_config.as_ref()?;
+        Some(match app_config.backend {
+            BackendKind::GraphQL => {
+                Box::new(GraphQLBackend::new(app_config.graphql_endpoint.clone(), self.token.clone()))
+            },
+            BackendKind::LocalStorage => Box::new(LocalStorageBackend),
+        })
+    }
+
+    // `LocalStorageBackend` can be used without logging in.
+    fn user_id(&self) -> Option<String> {
+        match (&self.user, &self.app_config.as_ref()?.backend) {
+            (Some(user), _) => Some(user.sub.clone()),
+            (None, BackendKind::LocalStorage) => Some(LOCAL_USER_ID.to_owned()),
+            (None, BackendKind::GraphQL) => None,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -67,38 +149,198 @@ struct User {
     sub: String,
 }
 
+#[derive(Deserialize)]
+struct Auth {
+    user: User,
+    token: String,
+}
+
+// ------ RunningTimer ------
+
+struct RunningTimer {
+    projects: Vec<TimerProject>,
+    time_entry: Option<RunningTimeEntry>,
+    timer_handle: Option<StreamHandle>,
+    last_activity: DateTime<Local>,
+    idle: Option<Idle>,
+}
+
+struct Idle {
+    since: DateTime<Local>,
+    // The user is back and has to decide what to do with the idle time.
+    returned: bool,
+}
+
+struct TimerProject {
+    client_id: Ulid,
+    project_id: Ulid,
+    client_name: String,
+    project_name: String,
+    last_time_entry_name: String,
+}
+
+#[derive(Clone)]
+struct RunningTimeEntry {
+    client_id: Ulid,
+    project_id: Ulid,
+    time_entry_id: Ulid,
+    name: String,
+    started: DateTime<Local>,
+}
+
+// Notified by the Time Tracker page when its running time entry has been changed.
+#[derive(Clone)]
+struct RunningTimeEntryChanged(Option<RunningTimeEntry>);
+
+impl RunningTimer {
+    fn set_time_entry(&mut self, time_entry: Option<RunningTimeEntry>, orders: &mut impl Orders<Msg>) {
+        self.timer_handle = time_entry.as_ref().map(|_| {
+            orders.stream_with_handle(streams::interval(1000, || Msg::OnRunningTimerTick))
+        });
+        if time_entry.is_none() {
+            self.idle = None;
+        }
+        self.time_entry = time_entry;
+        self.update_title_and_favicon();
+    }
+
+    // The tab shows the running timer even when the app isn't visible.
+    fn update_title_and_favicon(&self) {
+        let (title, favicon) = match &self.time_entry {
+            Some(time_entry) => {
+                let duration = format_running_duration(time_entry.started);
+                let title = match self.project(time_entry.project_id) {
+                    Some(project) => format!("▶ {} – {}", duration, project.project_name),
+                    None => format!("▶ {}", duration),
+                };
+                (title, FAVICON_RECORDING)
+            },
+            None => (TITLE.to_owned(), FAVICON),
+        };
+        let document = document();
+        document.set_title(&title);
+        if let Some(favicon_link) = document.get_element_by_id(FAVICON_ID) {
+            if favicon_link.get_attribute("href").as_deref() != Some(favicon) {
+                favicon_link.set_attribute("href", favicon).expect("set favicon href");
+            }
+        }
+    }
+
+    fn project(&self, project_id: Ulid) -> Option<&TimerProject> {
+        self.projects.iter().find(|project| project.project_id == project_id)
+    }
+}
+
 // ------ Page ------
 
 enum Page {
+    Loading,
+    // The `String` is the fetch or parse error.
+    AppConfigError(String),
     Home,
     ClientsAndProjects(page::clients_and_projects::Model),
     TimeTracker(page::time_tracker::Model),
     TimeBlocks(page::time_blocks::Model),
+    Reports(page::reports::Model),
+    Timesheet(page::timesheet::Model),
+    Calendar(page::calendar::Model),
+    Import(page::import::Model),
     Settings(page::settings::Model),
     NotFound,
 }
 
 impl Page {
-    fn init(mut url: Url, orders: &mut impl Orders<Msg>) -> Self {
+    fn init(mut url: Url, orders: &mut impl Orders<Msg>, ctx: &Context) -> Self {
         match url.remaining_path_parts().as_slice() {
             [] => Self::Home,
             [CLIENTS_AND_PROJECTS] => Self::ClientsAndProjects(
-                page::clients_and_projects::init(url, &mut orders.proxy(Msg::ClientsAndProjectsMsg))
+                page::clients_and_projects::init(url, &mut orders.proxy(Msg::ClientsAndProjectsMsg), ctx)
             ),
             [TIME_TRACKER] => Self::TimeTracker(
-                page::time_tracker::init(url, &mut orders.proxy(Msg::TimeTrackerMsg))
+                page::time_tracker::init(url, &mut orders.proxy(Msg::TimeTrackerMsg), ctx)
             ),
             [TIME_BLOCKS] => Self::TimeBlocks(
-                page::time_blocks::init(url, &mut orders.proxy(Msg::TimeBlocksMsg))
+                page::time_blocks::init(url, &mut orders.proxy(Msg::TimeBlocksMsg), ctx)
+            ),
+            [REPORTS] => Self::Reports(
+                page::reports::init(url, &mut orders.proxy(Msg::ReportsMsg), ctx)
+            ),
+            [TIMESHEET] => Self::Timesheet(
+                page::timesheet::init(url, &mut orders.proxy(Msg::TimesheetMsg), ctx)
+            ),
+            [CALENDAR] => Self::Calendar(
+                page::calendar::init(url, &mut orders.proxy(Msg::CalendarMsg), ctx)
+            ),
+            [IMPORT] => Self::Import(
+                page::import::init(url, &mut orders.proxy(Msg::ImportMsg), ctx)
             ),
             [SETTINGS] => Self::Settings(
-                page::settings::init(url, &mut orders.proxy(Msg::SettingsMsg))
+                page::settings::init(url, &mut orders.proxy(Msg::SettingsMsg), ctx)
             ),
             _ => Self::NotFound,
         }
     }
 }
 
+// ------ AppConfig ------
+
+#[derive(Deserialize)]
+struct AppConfig {
+    graphql_endpoint: String,
+    #[serde(default)]
+    backend: BackendKind,
+    #[serde(default = "default_idle_threshold_minutes")]
+    idle_threshold_minutes: i64,
+}
+
+fn default_idle_threshold_minutes() -> i64 {
+    10
+}
+
+#[derive(Deserialize)]
+struct AppConfigOverride {
+    graphql_endpoint: Option<String>,
+    backend: Option<BackendKind>,
+    idle_threshold_minutes: Option<i64>,
+}
+
+#[derive(Deserialize, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+enum BackendKind {
+    #[serde(rename = "graphql")]
+    GraphQL,
+    LocalStorage,
+}
+
+impl Default for BackendKind {
+    fn default() -> Self {
+        Self::GraphQL
+    }
+}
+
+async fn fetch_app_config() -> fetch::Result<AppConfig> {
+    let mut app_config: AppConfig = 
+        fetch(APP_CONFIG_PATH).await?.check_status()?.json().await?;
+
+    // The override file is optional - e.g. a local Dgraph instance or a staging server.
+    let app_config_override: fetch::Result<AppConfigOverride> = 
+        async { fetch(APP_CONFIG_OVERRIDE_PATH).await?.check_status()?.json().await }.await;
+
+    if let Ok(app_config_override) = app_config_override {
+        if let Some(graphql_endpoint) = app_config_override.graphql_endpoint {
+            app_config.graphql_endpoint = graphql_endpoint;
+        }
+        if let Some(backend) = app_config_override.backend {
+            app_config.backend = backend;
+        }
+        if let Some(idle_threshold_minutes) = app_config_override.idle_threshold_minutes {
+            app_config.idle_threshold_minutes = idle_threshold_minutes;
+        }
+    }
+    app_config.idle_threshold_minutes = app_config.idle_threshold_minutes.max(MIN_IDLE_THRESHOLD_MINUTES);
+    Ok(app_config)
+}
+
 // ------ AuthConfig ------
 
 #[derive(Deserialize)]
@@ -125,6 +367,18 @@ impl<'a> Urls<'a> {
     fn time_blocks(self) -> Url {
         self.base_url().add_path_part(TIME_BLOCKS)
     }
+    fn reports(self) -> Url {
+        self.base_url().add_path_part(REPORTS)
+    }
+    fn timesheet(self) -> Url {
+        self.base_url().add_path_part(TIMESHEET)
+    }
+    fn calendar(self) -> Url {
+        self.base_url().add_path_part(CALENDAR)
+    }
+    fn import(self) -> Url {
+        self.base_url().add_path_part(IMPORT)
+    }
     fn settings(self) -> Url {
         self.base_url().add_path_part(SETTINGS)
     }
@@ -138,6 +392,7 @@ enum Msg {
     UrlChanged(subs::UrlChanged),
     ToggleMenu,
     HideMenu,
+    AppConfigFetched(fetch::Result<AppConfig>),
     AuthConfigFetched(fetch::Result<AuthConfig>),
     AuthInitialized(Result<JsValue, JsValue>),
     SignUp,
@@ -145,18 +400,50 @@ enum Msg {
     LogOut,
     RedirectingToSignUp(Result<(), JsValue>),
     RedirectingToLogIn(Result<(), JsValue>),
+    MutationQueueChanged,
+    FetchRunningTimer,
+    RunningTimerFetched(graphql::Result<Vec<graphql::queries::clients_with_projects_with_time_entries::Client>>),
+    RunningTimeEntryChanged(Option<RunningTimeEntry>),
+    RunningTimerSaved(Option<graphql::GraphQLError>),
+    // The `RunningTimeEntry` is the running time entry before the idle time was discarded.
+    IdleTimeDiscarded(Option<graphql::GraphQLError>, RunningTimeEntry),
+    StopRunningTimer(DateTime<Local>),
+    SwitchRunningTimer(Ulid, Ulid),
+    OnRunningTimerTick,
+    OnUserActivity,
+    OnVisibilityChange,
+    KeepIdleTime,
+    DiscardIdleTime,
+    StopAtIdleStart,
+    ReplayMutationQueue,
+    MutationQueueReplayed(Result<Vec<graphql::GraphQLError>, graphql::GraphQLError>),
+    OnOnline,
 
     // ------ pages ------
 
     ClientsAndProjectsMsg(page::clients_and_projects::Msg),
     TimeTrackerMsg(page::time_tracker::Msg),
     TimeBlocksMsg(page::time_blocks::Msg),
+    ReportsMsg(page::reports::Msg),
+    TimesheetMsg(page::timesheet::Msg),
+    CalendarMsg(page::calendar::Msg),
+    ImportMsg(page::import::Msg),
     SettingsMsg(page::settings::Msg),
 }
 
 fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
-        Msg::UrlChanged(subs::UrlChanged(url)) => model.page = Page::init(url, orders),
+        Msg::UrlChanged(subs::UrlChanged(url)) => {
+            if model.ctx.app_config.is_some() {
+                // The running time entry is kept current by `RunningTimeEntryChanged`,
+                // only clients and projects for the timer have to be refreshed.
+                let projects_changed = matches!(model.page, Page::ClientsAndProjects(_) | Page::Import(_));
+                model.page = Page::init(url, orders, &model.ctx);
+                if projects_changed {
+                    orders.send_msg(Msg::FetchRunningTimer);
+                }
+            }
+        },
         Msg::ToggleMenu => model.menu_visible = not(model.menu_visible),
         Msg::HideMenu => {
             if model.menu_visible {
@@ -165,6 +452,17 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 orders.skip();
             }
         },
+        Msg::AppConfigFetched(Ok(app_config)) => {
+            model.ctx.app_config = Some(app_config);
+            model.page = Page::init(Url::current(), orders, &model.ctx);
+            orders
+                .send_msg(Msg::FetchRunningTimer)
+                .send_msg(Msg::ReplayMutationQueue);
+        },
+        Msg::AppConfigFetched(Err(fetch_error)) => {
+            model.page = Page::AppConfigError(format!("{:?}", fetch_error));
+            error!("AppConfig fetch failed!", fetch_error);
+        },
         Msg::AuthConfigFetched(Ok(auth_config)) => {
             let domain = auth_config.domain.clone();
             let client_id = auth_config.client_id.clone();
@@ -174,12 +472,19 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             )});
             model.auth_config = Some(auth_config);
         },
-        Msg::AuthConfigFetched(Err(fetch_error)) => error!("AuthConfig fetch failed!", fetch_error),
-        Msg::AuthInitialized(Ok(user)) => {
-            if not(user.is_undefined()) {
-                match serde_wasm_bindgen::from_value(user) {
-                    Ok(user) => model.ctx.user = Some(user),
-                    Err(error) => error!("User deserialization failed!", error),
+        Msg::AuthConfigFetched(Err(fetch_error)) => {
+            error!("AuthConfig fetch failed!", fetch_error);
+            model.ctx.auth_initialized = true;
+            reinit_page(model, orders);
+        },
+        Msg::AuthInitialized(Ok(auth)) => {
+            if not(auth.is_undefined()) {
+                match serde_wasm_bindgen::from_value::<Auth>(auth) {
+                    Ok(auth) => {
+                        model.ctx.user = Some(auth.user);
+                        model.ctx.token = Some(auth.token);
+                    },
+                    Err(error) => error!("Auth deserialization failed!", error),
                 }
             }
 
@@ -187,9 +492,14 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             if search.remove("code").is_some() && search.remove("state").is_some() {        
                 model.base_url.go_and_replace();
             }
+            model.ctx.auth_initialized = true;
+            reinit_page(model, orders);
+            orders.send_msg(Msg::ReplayMutationQueue);
         }
         Msg::AuthInitialized(Err(error)) => {
             error!("Auth initialization failed!", error);
+            model.ctx.auth_initialized = true;
+            reinit_page(model, orders);
         }
         Msg::SignUp => {
             orders.perform_cmd(async { Msg::RedirectingToSignUp(
@@ -216,34 +526,346 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 error!("Cannot log out!", error);
             } else {
                 model.ctx.user = None;
+                model.ctx.token = None;
+                // Don't leave the previous user's timer and data on the screen.
+                model.running_timer.projects.clear();
+                model.running_timer.set_time_entry(None, orders);
+                reinit_page(model, orders);
+            }
+        },
+        Msg::MutationQueueChanged => {
+            model.mutation_queue.len = graphql::queue::len();
+            if model.mutation_queue.replay_handle.is_none() {
+                schedule_replay(&mut model.mutation_queue, orders);
+            }
+        },
+        Msg::ReplayMutationQueue => {
+            let mutation_queue = &mut model.mutation_queue;
+            mutation_queue.replay_handle = None;
+            mutation_queue.len = graphql::queue::len();
+
+            if mutation_queue.replaying || mutation_queue.len == 0 || not(model.ctx.auth_initialized) {
+                return;
+            }
+            if let Some(app_config) = &model.ctx.app_config {
+                let graphql_endpoint = app_config.graphql_endpoint.clone();
+                let token = model.ctx.token.clone();
+                mutation_queue.replaying = true;
+                orders.perform_cmd(async { Msg::MutationQueueReplayed(
+                    graphql::queue::replay(graphql_endpoint, token).await
+                )});
+            }
+        },
+        Msg::MutationQueueReplayed(result) => {
+            let mutation_queue = &mut model.mutation_queue;
+            mutation_queue.replaying = false;
+            mutation_queue.len = graphql::queue::len();
+
+            match result {
+                Ok(rejected) => {
+                    for graphql_error in rejected {
+                        error!("Queued mutation rejected!", graphql_error.to_string());
+                    }
+                    mutation_queue.replay_delay_ms = MIN_REPLAY_DELAY_MS;
+                    // Mutations may have been queued while the replay was running.
+                    if mutation_queue.len > 0 {
+                        orders.send_msg(Msg::ReplayMutationQueue);
+                    }
+                },
+                Err(_) => {
+                    schedule_replay(mutation_queue, orders);
+                    mutation_queue.replay_delay_ms = (mutation_queue.replay_delay_ms * 2).min(MAX_REPLAY_DELAY_MS);
+                },
+            }
+        },
+
+        Msg::FetchRunningTimer => {
+            if let (Some(user_id), Some(backend)) = (model.ctx.user_id(), model.ctx.backend()) {
+                let args = graphql::queries::clients_with_projects_with_time_entries::ClientsArguments { user: user_id };
+                orders.perform_cmd(async move { Msg::RunningTimerFetched(
+                    backend.clients_with_projects_with_time_entries(args).await
+                )});
+            } else {
+                model.running_timer.projects.clear();
+                model.running_timer.set_time_entry(None, orders);
             }
         },
+        Msg::RunningTimerFetched(Ok(clients)) => {
+            let mut projects = Vec::new();
+            let mut time_entry = None;
+
+            for client in clients {
+                let client_id = client.id.parse().expect("parse client Ulid");
+                for project in client.projects {
+                    let project_id = project.id.parse().expect("parse project Ulid");
+
+                    let mut time_entries = project.time_entries;
+                    time_entries.sort_by(|time_entry_a, time_entry_b| time_entry_a.id.cmp(&time_entry_b.id));
+
+                    if let Some(running_time_entry) = time_entries.iter().find(|time_entry| time_entry.stopped.is_none()) {
+                        time_entry = Some(RunningTimeEntry {
+                            client_id,
+                            project_id,
+                            time_entry_id: running_time_entry.id.parse().expect("parse time_entry Ulid"),
+                            name: running_time_entry.name.clone(),
+                            started: running_time_entry.started.0.parse().expect("parse time_entry started time"),
+                        });
+                    }
+                    projects.push(TimerProject {
+                        client_id,
+                        project_id,
+                        client_name: client.name.clone(),
+                        project_name: project.name,
+                        last_time_entry_name: time_entries.pop().map(|time_entry| time_entry.name).unwrap_or_default(),
+                    });
+                }
+            }
+            model.running_timer.projects = projects;
+            model.running_timer.set_time_entry(time_entry, orders);
+        },
+        Msg::RunningTimerFetched(Err(graphql_error)) => {
+            error!("Running timer fetch failed!", graphql_error.to_string());
+        },
+        Msg::RunningTimeEntryChanged(time_entry) => {
+            model.running_timer.set_time_entry(time_entry, orders);
+        },
+        Msg::RunningTimerSaved(None) => (),
+        Msg::RunningTimerSaved(Some(graphql::GraphQLError::Queued)) => {
+            orders.send_msg(Msg::MutationQueueChanged);
+        },
+        Msg::RunningTimerSaved(Some(graphql_error)) => {
+            error!("Running timer change failed!", graphql_error.to_string());
+            orders.send_msg(Msg::FetchRunningTimer);
+        },
+        Msg::IdleTimeDiscarded(None, _) => (),
+        Msg::IdleTimeDiscarded(Some(graphql::GraphQLError::Queued), _) => {
+            orders.send_msg(Msg::MutationQueueChanged);
+        },
+        Msg::IdleTimeDiscarded(Some(graphql_error), previous_time_entry) => {
+            error!("Idle time discarding failed!", graphql_error.to_string());
+            model.running_timer.set_time_entry(Some(previous_time_entry), orders);
+            orders.send_msg(Msg::FetchRunningTimer);
+        },
+        Msg::StopRunningTimer(stopped) => {
+            // The Time Tracker page owns its time entries, so it has to make the change itself.
+            match (&model.page, &model.running_timer.time_entry) {
+                (Page::TimeTracker(_), Some(time_entry)) => {
+                    orders.send_msg(Msg::TimeTrackerMsg(
+                        page::time_tracker::Msg::StopAt(time_entry.client_id, time_entry.project_id, stopped)
+                    ));
+                },
+                _ => stop_running_timer(&mut model.running_timer, &model.ctx, orders, stopped),
+            }
+        },
+        Msg::SwitchRunningTimer(client_id, project_id) => {
+            if let Page::TimeTracker(_) = model.page {
+                orders.send_msg(Msg::TimeTrackerMsg(page::time_tracker::Msg::Start(client_id, project_id)));
+                return;
+            }
+            stop_running_timer(&mut model.running_timer, &model.ctx, orders, Local::now());
+
+            let name = match model.running_timer.project(project_id) {
+                Some(project) => project.last_time_entry_name.clone(),
+                None => return,
+            };
+            let time_entry = RunningTimeEntry {
+                client_id,
+                project_id,
+                time_entry_id: Ulid::new(),
+                name,
+                started: Local::now(),
+            };
+            if let Some(backend) = model.ctx.backend() {
+                let args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
+                    id: time_entry.time_entry_id.to_string(),
+                    name: time_entry.name.clone(),
+                    started: time_entry.started,
+                    stopped: None,
+                    project: project_id.to_string(),
+                };
+                orders.perform_cmd(async move { Msg::RunningTimerSaved(
+                    backend.add_time_entry(args).await.err()
+                )});
+            }
+            model.running_timer.set_time_entry(Some(time_entry), orders);
+        },
+        Msg::OnRunningTimerTick => {
+            let running_timer = &mut model.running_timer;
+            running_timer.update_title_and_favicon();
+
+            if let (None, Some(app_config)) = (&running_timer.idle, &model.ctx.app_config) {
+                let idle_threshold = chrono::Duration::minutes(app_config.idle_threshold_minutes);
+                if Local::now() - running_timer.last_activity > idle_threshold {
+                    running_timer.idle = Some(Idle { since: running_timer.last_activity, returned: false });
+                }
+            }
+        },
+        Msg::OnUserActivity => {
+            let running_timer = &mut model.running_timer;
+            running_timer.last_activity = Local::now();
+            match &mut running_timer.idle {
+                Some(idle) if not(idle.returned) => idle.returned = true,
+                _ => { orders.skip(); },
+            }
+        },
+        Msg::OnVisibilityChange => {
+            if document().hidden() {
+                orders.skip();
+            } else {
+                orders.send_msg(Msg::OnUserActivity);
+            }
+        },
+        Msg::KeepIdleTime => {
+            model.running_timer.idle = None;
+        },
+        Msg::DiscardIdleTime => {
+            let running_timer = &mut model.running_timer;
+            if let (Some(idle), Some(time_entry)) = (running_timer.idle.take(), &running_timer.time_entry) {
+                // The Time Tracker page owns its time entries, so it has to make the change itself.
+                if let Page::TimeTracker(_) = model.page {
+                    orders.send_msg(Msg::TimeTrackerMsg(
+                        page::time_tracker::Msg::DiscardIdleTime(time_entry.client_id, time_entry.project_id, idle.since)
+                    ));
+                } else {
+                    discard_idle_time(running_timer, &model.ctx, orders, idle.since);
+                }
+            }
+        },
+        Msg::StopAtIdleStart => {
+            if let Some(idle) = model.running_timer.idle.take() {
+                orders.send_msg(Msg::StopRunningTimer(idle.since));
+            }
+        },
+        Msg::OnOnline => {
+            model.mutation_queue.replay_delay_ms = MIN_REPLAY_DELAY_MS;
+            orders.send_msg(Msg::ReplayMutationQueue);
+        },
 
         // ------ pages ------
 
         Msg::ClientsAndProjectsMsg(msg) => {
-            if let Page::ClientsAndProjects(model) = &mut model.page {
-                page::clients_and_projects::update(msg, model, &mut orders.proxy(Msg::ClientsAndProjectsMsg))
+            if let Page::ClientsAndProjects(page_model) = &mut model.page {
+                page::clients_and_projects::update(msg, page_model, &model.ctx, &mut orders.proxy(Msg::ClientsAndProjectsMsg))
             }
         }
         Msg::TimeTrackerMsg(msg) => {
-            if let Page::TimeTracker(model) = &mut model.page {
-                page::time_tracker::update(msg, model, &mut orders.proxy(Msg::TimeTrackerMsg))
+            if let Page::TimeTracker(page_model) = &mut model.page {
+                page::time_tracker::update(msg, page_model, &model.ctx, &mut orders.proxy(Msg::TimeTrackerMsg))
             }
         },
         Msg::TimeBlocksMsg(msg) => {
-            if let Page::TimeBlocks(model) = &mut  model.page {
-                page::time_blocks::update(msg, model, &mut orders.proxy(Msg::TimeBlocksMsg))
+            if let Page::TimeBlocks(page_model) = &mut model.page {
+                page::time_blocks::update(msg, page_model, &model.ctx, &mut orders.proxy(Msg::TimeBlocksMsg))
+            }
+        }
+        Msg::ReportsMsg(msg) => {
+            if let Page::Reports(page_model) = &mut model.page {
+                page::reports::update(msg, page_model, &model.ctx, &mut orders.proxy(Msg::ReportsMsg))
+            }
+        }
+        Msg::TimesheetMsg(msg) => {
+            if let Page::Timesheet(page_model) = &mut model.page {
+                page::timesheet::update(msg, page_model, &model.ctx, &mut orders.proxy(Msg::TimesheetMsg))
+            }
+        }
+        Msg::CalendarMsg(msg) => {
+            if let Page::Calendar(page_model) = &mut model.page {
+                page::calendar::update(msg, page_model, &model.ctx, &mut orders.proxy(Msg::CalendarMsg))
+            }
+        }
+        Msg::ImportMsg(msg) => {
+            if let Page::Import(page_model) = &mut model.page {
+                page::import::update(msg, page_model, &model.ctx, &mut orders.proxy(Msg::ImportMsg))
             }
         }
         Msg::SettingsMsg(msg) => {
-            if let Page::Settings(model) = &mut model.page {
-                page::settings::update(msg, model, &mut orders.proxy(Msg::SettingsMsg))
+            if let Page::Settings(page_model) = &mut model.page {
+                page::settings::update(msg, page_model, &model.ctx, &mut orders.proxy(Msg::SettingsMsg))
             }
         }
     }
 }
 
+fn stop_running_timer(
+    running_timer: &mut RunningTimer, 
+    ctx: &Context, 
+    orders: &mut impl Orders<Msg>, 
+    stopped: DateTime<Local>,
+) {
+    let time_entry = match running_timer.time_entry.take() {
+        Some(time_entry) => time_entry,
+        None => return,
+    };
+    if let Some(backend) = ctx.backend() {
+        let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+            id: time_entry.time_entry_id.to_string(),
+            started: time_entry.started,
+            stopped: Some(stopped),
+        };
+        orders.perform_cmd(async move { Msg::RunningTimerSaved(
+            backend.set_time_entry_times(args).await.err()
+        )});
+    }
+    if let Some(project) = running_timer.projects.iter_mut().find(|project| project.project_id == time_entry.project_id) {
+        project.last_time_entry_name = time_entry.name;
+    }
+    running_timer.set_time_entry(None, orders);
+}
+
+// The running time entry is stopped at the start of the idle time 
+// and a new one with the same name starts now.
+fn discard_idle_time(
+    running_timer: &mut RunningTimer, 
+    ctx: &Context, 
+    orders: &mut impl Orders<Msg>, 
+    idle_since: DateTime<Local>,
+) {
+    let previous_time_entry = match &running_timer.time_entry {
+        Some(time_entry) => time_entry.clone(),
+        None => return,
+    };
+    let time_entry = RunningTimeEntry {
+        time_entry_id: Ulid::new(),
+        started: Local::now(),
+        ..previous_time_entry.clone()
+    };
+    if let Some(backend) = ctx.backend() {
+        let set_times_args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+            id: previous_time_entry.time_entry_id.to_string(),
+            started: previous_time_entry.started,
+            stopped: Some(idle_since.max(previous_time_entry.started)),
+        };
+        let add_args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
+            id: time_entry.time_entry_id.to_string(),
+            name: time_entry.name.clone(),
+            started: time_entry.started,
+            stopped: None,
+            project: time_entry.project_id.to_string(),
+        };
+        orders.perform_cmd(async move { 
+            let error = match backend.set_time_entry_times(set_times_args).await {
+                Ok(()) | Err(graphql::GraphQLError::Queued) => backend.add_time_entry(add_args).await.err(),
+                Err(error) => Some(error),
+            };
+            Msg::IdleTimeDiscarded(error, previous_time_entry)
+        });
+    }
+    running_timer.set_time_entry(Some(time_entry), orders);
+}
+
+fn schedule_replay(mutation_queue: &mut MutationQueue, orders: &mut impl Orders<Msg>) {
+    mutation_queue.replay_handle = Some(orders.perform_cmd_with_handle(
+        cmds::timeout(mutation_queue.replay_delay_ms, || Msg::ReplayMutationQueue)
+    ));
+}
+
+fn reinit_page(model: &mut Model, orders: &mut impl Orders<Msg>) {
+    if model.ctx.app_config.is_some() {
+        model.page = Page::init(Url::current(), orders, &model.ctx);
+        orders.send_msg(Msg::FetchRunningTimer);
+    }
+}
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(catch)]
@@ -265,22 +887,69 @@ extern "C" {
 
 fn view(model: &Model) -> Vec<Node<Msg>> {
     vec![
-        view_navbar(model.menu_visible, &model.base_url, model.ctx.user.as_ref(), &model.page),
-        view_content(&model.page, &model.base_url),
+        view_navbar(model.menu_visible, &model.base_url, model.ctx.user.as_ref(), &model.page, &model.mutation_queue, &model.running_timer),
+        view_content(&model.page, &model.base_url, &model.ctx),
+        view_idle_modal(model.running_timer.idle.as_ref()),
+    ]
+}
+
+// ----- view_idle_modal ------
+
+fn view_idle_modal(idle: Option<&Idle>) -> Node<Msg> {
+    let idle = match idle {
+        Some(idle) if idle.returned => idle,
+        _ => return empty![],
+    };
+    let idle_minutes = (Local::now() - idle.since).num_minutes();
+
+    div![C!["modal", "is-active"],
+        div![C!["modal-background"]],
+        div![C!["modal-card"],
+            header![C!["modal-card-head"],
+                p![C!["modal-card-title"], "You have been idle"],
+            ],
+            section![C!["modal-card-body"],
+                format!(
+                    "The timer is still running, but there has been no activity since {} ({} min).", 
+                    idle.since.format("%H:%M"), 
+                    idle_minutes,
+                ),
+            ],
+            footer![C!["modal-card-foot"],
+                button![C!["button", "is-primary"],
+                    "Keep",
+                    ev(Ev::Click, |_| Msg::KeepIdleTime),
+                ],
+                button![C!["button", "is-link"],
+                    "Discard idle time",
+                    ev(Ev::Click, |_| Msg::DiscardIdleTime),
+                ],
+                button![C!["button", "is-warning"],
+                    format!("Stop at {}", idle.since.format("%H:%M")),
+                    ev(Ev::Click, |_| Msg::StopAtIdleStart),
+                ],
+            ],
+        ],
     ]
 }
 
 // ----- view_content ------
 
-fn view_content(page: &Page, base_url: &Url) -> Node<Msg> {
+fn view_content(page: &Page, base_url: &Url, ctx: &Context) -> Node<Msg> {
     div![
         C!["container"],
         match page {
+            Page::Loading => progress![C!["progress", "is-link", "mt-6"]],
+            Page::AppConfigError(error) => page::app_config_error::view(APP_CONFIG_PATH, error),
             Page::Home => page::home::view(base_url),
-            Page::ClientsAndProjects(model) => page::clients_and_projects::view(model).map_msg(Msg::ClientsAndProjectsMsg),
-            Page::TimeTracker(model) => page::time_tracker::view(model).map_msg(Msg::TimeTrackerMsg),
-            Page::TimeBlocks(model) => page::time_blocks::view(model).map_msg(Msg::TimeBlocksMsg),
-            Page::Settings(model) => page::settings::view(model).map_msg(Msg::SettingsMsg),
+            Page::ClientsAndProjects(model) => page::clients_and_projects::view(model, ctx).map_msg(Msg::ClientsAndProjectsMsg),
+            Page::TimeTracker(model) => page::time_tracker::view(model, ctx).map_msg(Msg::TimeTrackerMsg),
+            Page::TimeBlocks(model) => page::time_blocks::view(model, ctx).map_msg(Msg::TimeBlocksMsg),
+            Page::Reports(model) => page::reports::view(model, ctx).map_msg(Msg::ReportsMsg),
+            Page::Timesheet(model) => page::timesheet::view(model, ctx).map_msg(Msg::TimesheetMsg),
+            Page::Calendar(model) => page::calendar::view(model, ctx).map_msg(Msg::CalendarMsg),
+            Page::Import(model) => page::import::view(model, ctx).map_msg(Msg::ImportMsg),
+            Page::Settings(model) => page::settings::view(model, ctx).map_msg(Msg::SettingsMsg),
             Page::NotFound => page::not_found::view(),
         }
     ]
@@ -288,7 +957,14 @@ fn view_content(page: &Page, base_url: &Url) -> Node<Msg> {
 
 // ----- view_navbar ------
 
-fn view_navbar(menu_visible: bool, base_url: &Url, user: Option<&User>, page: &Page) -> Node<Msg> {
+fn view_navbar(
+    menu_visible: bool, 
+    base_url: &Url, 
+    user: Option<&User>, 
+    page: &Page, 
+    mutation_queue: &MutationQueue,
+    running_timer: &RunningTimer,
+) -> Node<Msg> {
     nav![
         C!["navbar", "is-link"],
         attrs!{
@@ -296,7 +972,7 @@ fn view_navbar(menu_visible: bool, base_url: &Url, user: Option<&User>, page: &P
             At::AriaLabel => "main navigation",
         },
         view_brand_and_hamburger(menu_visible, base_url),
-        view_navbar_menu(menu_visible, base_url, user, page),
+        view_navbar_menu(menu_visible, base_url, user, page, mutation_queue, running_timer),
     ]
 }
 
@@ -332,11 +1008,18 @@ fn view_brand_and_hamburger(menu_visible: bool, base_url: &Url) -> Node<Msg> {
     ]
 }
 
-fn view_navbar_menu(menu_visible: bool, base_url: &Url, user: Option<&User>, page: &Page) -> Node<Msg> {
+fn view_navbar_menu(
+    menu_visible: bool, 
+    base_url: &Url, 
+    user: Option<&User>, 
+    page: &Page, 
+    mutation_queue: &MutationQueue,
+    running_timer: &RunningTimer,
+) -> Node<Msg> {
     div![
         C!["navbar-menu", IF!(menu_visible => "is-active")],
         view_navbar_menu_start(base_url, page),
-        view_navbar_menu_end(base_url, user),
+        view_navbar_menu_end(base_url, user, mutation_queue, running_timer),
     ]
 }
 
@@ -348,6 +1031,16 @@ fn view_navbar_menu_start(base_url: &Url, page: &Page) -> Node<Msg> {
             attrs!{At::Href => Urls::new(base_url).time_tracker()},
             "Time Tracker",
         ],
+        a![
+            C!["navbar-item", IF!(matches!(page, Page::Timesheet(_)) => "is-active"),],
+            attrs!{At::Href => Urls::new(base_url).timesheet()},
+            "Timesheet",
+        ],
+        a![
+            C!["navbar-item", IF!(matches!(page, Page::Calendar(_)) => "is-active"),],
+            attrs!{At::Href => Urls::new(base_url).calendar()},
+            "Calendar",
+        ],
         a![
             C!["navbar-item", IF!(matches!(page, Page::ClientsAndProjects(_)) => "is-active"),],
             attrs!{At::Href => Urls::new(base_url).clients_and_projects()},
@@ -358,12 +1051,29 @@ fn view_navbar_menu_start(base_url: &Url, page: &Page) -> Node<Msg> {
             attrs!{At::Href => Urls::new(base_url).time_blocks()},
             "Time Blocks",
         ],
+        a![
+            C!["navbar-item", IF!(matches!(page, Page::Reports(_)) => "is-active"),],
+            attrs!{At::Href => Urls::new(base_url).reports()},
+            "Reports",
+        ],
+        a![
+            C!["navbar-item", IF!(matches!(page, Page::Import(_)) => "is-active"),],
+            attrs!{At::Href => Urls::new(base_url).import()},
+            "Import",
+        ],
     ]
 }
 
-fn view_navbar_menu_end(base_url: &Url, user: Option<&User>) -> Node<Msg> {
+fn view_navbar_menu_end(
+    base_url: &Url, 
+    user: Option<&User>, 
+    mutation_queue: &MutationQueue,
+    running_timer: &RunningTimer,
+) -> Node<Msg> {
      div![
         C!["navbar-end"],
+        IF!(not(running_timer.projects.is_empty()) => view_running_timer(running_timer)),
+        IF!(mutation_queue.len > 0 => view_mutation_queue(mutation_queue)),
         div![
             C!["navbar-item"],
             div![
@@ -378,6 +1088,79 @@ fn view_navbar_menu_end(base_url: &Url, user: Option<&User>) -> Node<Msg> {
     ]
 }
 
+fn view_running_timer(running_timer: &RunningTimer) -> Node<Msg> {
+    let running_project = running_timer
+        .time_entry
+        .as_ref()
+        .and_then(|time_entry| Some((time_entry, running_timer.project(time_entry.project_id)?)));
+
+    div![
+        C!["navbar-item", "has-dropdown", "is-hoverable"],
+        a![
+            C!["navbar-link"],
+            if let Some((time_entry, project)) = running_project {
+                nodes![
+                    span![C!["tag", "is-warning", "mr-2"],
+                        format_running_duration(time_entry.started),
+                    ],
+                    span![format!("{} / {}", project.client_name, project.project_name)],
+                    IF!(not(time_entry.name.is_empty()) => span![format!(" – {}", time_entry.name)]),
+                ]
+            } else {
+                nodes![span!["Not tracking"]]
+            }
+        ],
+        div![
+            C!["navbar-dropdown", "is-right"],
+            IF!(running_project.is_some() => vec![
+                a![
+                    C!["navbar-item", "has-text-weight-bold"],
+                    "Stop",
+                    ev(Ev::Click, |_| Msg::StopRunningTimer(Local::now())),
+                ],
+                hr![C!["navbar-divider"]],
+            ]),
+            div![C!["navbar-item", "heading", "mb-0"], 
+                if running_project.is_some() { "Switch to" } else { "Start" },
+            ],
+            running_timer.projects.iter().map(|project| {
+                let client_id = project.client_id;
+                let project_id = project.project_id;
+                let running = running_project.map(|(_, running_project)| running_project.project_id) == Some(project_id);
+                a![
+                    C!["navbar-item", IF!(running => "is-active")],
+                    format!("{} / {}", project.client_name, project.project_name),
+                    ev(Ev::Click, move |_| Msg::SwitchRunningTimer(client_id, project_id)),
+                ]
+            }),
+        ],
+    ]
+}
+
+// A start slightly in the future (e.g. another device's clock) is shown as zero.
+fn format_running_duration(started: DateTime<Local>) -> String {
+    page::common::format_duration(&(Local::now() - started).max(chrono::Duration::zero()))
+}
+
+fn view_mutation_queue(mutation_queue: &MutationQueue) -> Node<Msg> {
+    div![
+        C!["navbar-item"],
+        a![
+            C!["tag", "is-warning", "is-medium"],
+            attrs!{At::Title => "Click to send them now."},
+            format!(
+                "{} unsaved {}", 
+                mutation_queue.len, 
+                if mutation_queue.len == 1 { "change" } else { "changes" }
+            ),
+            ev(Ev::Click, |event| {
+                event.stop_propagation();
+                Msg::ReplayMutationQueue
+            }),
+        ]
+    ]
+}
+
 fn view_buttons_for_logged_in_user(base_url: &Url, user: &User) -> Vec<Node<Msg>> {
     vec![
         a![
@@ -1,6 +1,13 @@
+pub mod common;
 pub mod home;
 pub mod clients_and_projects;
 pub mod time_tracker;
 pub mod time_blocks;
+pub mod reports;
+pub mod timesheet;
+pub mod calendar;
+pub mod import;
 pub mod settings;
 pub mod not_found;
+pub mod app_config_error;
+pub mod log_in_required;
@@ -0,0 +1,15 @@
+use seed::{prelude::*, *};
+
+pub fn view<Ms>(path: &str, error: &str) -> Node<Ms> {
+    section![C!["hero", "is-medium", "ml-6"],
+        div![C!["hero-body"],
+            h1![C!["title", "is-size-3"],
+                format!("{} cannot be loaded", path),
+            ],
+            h2![C!["subtitle", "is-size-5"],
+                "Check that the file exists and contains valid configuration.",
+            ],
+            pre![error],
+        ]
+    ]
+}
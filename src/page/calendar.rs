@@ -0,0 +1,663 @@
+use seed::{prelude::*, *};
+
+use chrono::{prelude::*, Duration};
+use ulid::Ulid;
+
+use std::collections::BTreeMap;
+
+use crate::{backend::Backend, graphql, page, parser, Context, RunningTimeEntry, RunningTimeEntryChanged};
+use crate::page::common::{self, ChangesStatus, RemoteData};
+
+type ClientId = Ulid;
+type ProjectId = Ulid;
+type TimeEntryId = Ulid;
+
+const HOUR_HEIGHT_PX: f64 = 40.;
+const SNAP_MINUTES: i64 = 5;
+const MINUTES_PER_DAY: i64 = 24 * 60;
+const CLIENT_COLORS: [&str; 6] = ["#3273dc", "#00d1b2", "#b86bff", "#f14668", "#ff9f43", "#48c774"];
+
+// ------ ------
+//     Init
+// ------ ------
+
+pub fn init(_: Url, orders: &mut impl Orders<Msg>, ctx: &Context) -> Model {
+    let clients = match (ctx.user_id(), ctx.backend()) {
+        _ if not(ctx.auth_initialized) => RemoteData::Loading,
+        (Some(user_id), Some(backend)) => {
+            orders.perform_cmd(async move { Msg::ClientsFetched(
+                request_clients(backend, user_id).await
+            )});
+            RemoteData::Loading
+        },
+        _ => RemoteData::NotAsked,
+    };
+
+    Model {
+        changes_status: ChangesStatus::NoChanges,
+        errors: Vec::new(),
+        before_unload_handle: orders.stream_with_handle(streams::window_event(Ev::BeforeUnload, Msg::OnBeforeUnload)),
+
+        mode: Mode::Week,
+        date: Local::today().naive_local(),
+        new_time_entry_project: None,
+        drag: None,
+        drag_stream_handles: Vec::new(),
+        clients,
+    }
+}
+
+async fn request_clients(
+    backend: Box<dyn Backend>,
+    user_id: String,
+) -> graphql::Result<BTreeMap<ClientId, Client>> {
+    use graphql::queries::clients_with_projects_with_time_entries as query_mod;
+
+    let time_entry_mapper = |time_entry: query_mod::TimeEntry| (
+        time_entry.id.parse().expect("parse time_entry Ulid"),
+        TimeEntry {
+            name: time_entry.name,
+            started: time_entry.started.0.parse().expect("parse time_entry started time"),
+            stopped: time_entry.stopped.map(|time| time.0.parse().expect("parse time_entry stopped time")),
+        }
+    );
+
+    let project_mapper = |project: query_mod::Project| (
+        project.id.parse().expect("parse project Ulid"),
+        Project {
+            name: project.name,
+            time_entries: project.time_entries.into_iter().map(time_entry_mapper).collect(),
+        },
+    );
+
+    let client_mapper = |client: query_mod::Client| (
+        client.id.parse().expect("parse client Ulid"),
+        Client {
+            name: client.name,
+            projects: client.projects.into_iter().map(project_mapper).collect(),
+        }
+    );
+
+    let args = query_mod::ClientsArguments { user: user_id };
+    Ok(
+        backend
+            .clients_with_projects_with_time_entries(args)
+            .await?
+            .into_iter()
+            .map(client_mapper)
+            .collect()
+    )
+}
+
+// ------ ------
+//     Model
+// ------ ------
+
+pub struct Model {
+    changes_status: ChangesStatus,
+    errors: Vec<graphql::GraphQLError>,
+    before_unload_handle: StreamHandle,
+
+    mode: Mode,
+    // Any day in the displayed range.
+    date: NaiveDate,
+    // Target of time entries created by dragging on empty space.
+    // The first project is used when `None`.
+    new_time_entry_project: Option<(ClientId, ProjectId)>,
+    drag: Option<Drag>,
+    drag_stream_handles: Vec<StreamHandle>,
+    clients: RemoteData<BTreeMap<ClientId, Client>>,
+}
+
+impl Model {
+    fn days(&self) -> Vec<NaiveDate> {
+        match self.mode {
+            Mode::Day => vec![self.date],
+            Mode::Week => {
+                let monday = self.date - Duration::days(i64::from(self.date.weekday().num_days_from_monday()));
+                (0..7).map(|day| monday + Duration::days(day)).collect()
+            },
+        }
+    }
+
+    fn new_time_entry_project(&self) -> Option<(ClientId, ProjectId)> {
+        self.new_time_entry_project.or_else(|| {
+            if let RemoteData::Loaded(clients) = &self.clients {
+                clients.iter().find_map(|(client_id, client)| {
+                    client.projects.keys().next().map(|project_id| (*client_id, *project_id))
+                })
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Mode {
+    Day,
+    Week,
+}
+
+// ---- Drag ----
+
+#[derive(Debug, Copy, Clone)]
+pub struct Drag {
+    kind: DragKind,
+    date: NaiveDate,
+    start_client_y: i32,
+    // Minutes since midnight of `date`.
+    start_minutes: i64,
+    current_minutes: i64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DragKind {
+    Started(ClientId, ProjectId, TimeEntryId),
+    Stopped(ClientId, ProjectId, TimeEntryId),
+    NewTimeEntry,
+}
+
+impl Drag {
+    // Started and stopped for the new time entry while dragging on empty space.
+    fn new_time_entry_minutes(&self) -> (i64, i64) {
+        (self.start_minutes.min(self.current_minutes), self.start_minutes.max(self.current_minutes))
+    }
+}
+
+fn minutes_from_px(px: f64) -> i64 {
+    let minutes = (px / HOUR_HEIGHT_PX * 60.).round() as i64;
+    (minutes / SNAP_MINUTES * SNAP_MINUTES).max(0).min(MINUTES_PER_DAY)
+}
+
+fn px_from_minutes(minutes: i64) -> f64 {
+    minutes as f64 / 60. * HOUR_HEIGHT_PX
+}
+
+fn date_time(date: NaiveDate, minutes: i64) -> DateTime<Local> {
+    parser::local_midnight(date) + Duration::minutes(minutes)
+}
+
+// --- Entities ----
+
+#[derive(Debug)]
+pub struct Client {
+    name: String,
+    projects: BTreeMap<ProjectId, Project>,
+}
+
+#[derive(Debug)]
+pub struct Project {
+    name: String,
+    time_entries: BTreeMap<TimeEntryId, TimeEntry>,
+}
+
+#[derive(Debug)]
+pub struct TimeEntry {
+    name: String,
+    started: DateTime<Local>,
+    stopped: Option<DateTime<Local>>,
+}
+
+// ---- Undo ----
+
+pub enum Undo {
+    RemoveTimeEntry(ClientId, ProjectId, TimeEntryId),
+    SetTimeEntryTimes(ClientId, ProjectId, TimeEntryId, DateTime<Local>, Option<DateTime<Local>>),
+}
+
+impl common::Undo<BTreeMap<ClientId, Client>> for Undo {
+    fn apply(self, clients: &mut BTreeMap<ClientId, Client>) -> Option<()> {
+        match self {
+            Self::RemoveTimeEntry(client_id, project_id, time_entry_id) => {
+                clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .remove(&time_entry_id);
+            },
+            Self::SetTimeEntryTimes(client_id, project_id, time_entry_id, started, stopped) => {
+                let time_entry = clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .get_mut(&time_entry_id)?;
+
+                time_entry.started = started;
+                time_entry.stopped = stopped;
+            },
+        }
+        Some(())
+    }
+}
+
+// ------ ------
+//    Update
+// ------ ------
+
+pub enum Msg {
+    ClientsFetched(graphql::Result<BTreeMap<ClientId, Client>>),
+    ChangesSaved(Option<graphql::GraphQLError>, Option<Undo>),
+    OnBeforeUnload(web_sys::Event),
+    ClearErrors,
+
+    ModeSelected(Mode),
+    Previous,
+    Next,
+    Today,
+    // The `String` is in the format `[client id]/[project id]`.
+    NewTimeEntryProjectSelected(String),
+
+    // Start of a drag - the `DragKind`, the dragged day, the mouse `clientY` and minutes since midnight.
+    DragStarted(DragKind, NaiveDate, i32, i64),
+    OnDragMove(i32),
+    OnDragEnd,
+}
+
+pub fn update(msg: Msg, model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::ClientsFetched(Ok(clients)) => {
+            model.clients = RemoteData::Loaded(clients);
+        },
+        Msg::ClientsFetched(Err(graphql_error)) => {
+            model.clients = RemoteData::Failed;
+            model.errors.push(graphql_error);
+        },
+
+        Msg::ChangesSaved(error, undo) => {
+            common::changes_saved(error, undo, &mut model.clients, &mut model.changes_status, &mut model.errors, orders);
+        },
+        Msg::OnBeforeUnload(event) => {
+            model.changes_status.on_before_unload(event);
+            orders.skip();
+        },
+
+        Msg::ClearErrors => {
+            model.errors.clear();
+        },
+
+        // ------ Navigation ------
+
+        Msg::ModeSelected(mode) => {
+            model.mode = mode;
+        },
+        Msg::Previous => {
+            model.date = model.date - step(model.mode);
+        },
+        Msg::Next => {
+            model.date = model.date + step(model.mode);
+        },
+        Msg::Today => {
+            model.date = Local::today().naive_local();
+        },
+        Msg::NewTimeEntryProjectSelected(target) => {
+            let mut target = target.split('/');
+            let client_id = target.next().and_then(|id| id.parse().ok());
+            let project_id = target.next().and_then(|id| id.parse().ok());
+            if let (Some(client_id), Some(project_id)) = (client_id, project_id) {
+                model.new_time_entry_project = Some((client_id, project_id));
+            }
+        },
+
+        // ------ Drag ------
+
+        Msg::DragStarted(kind, date, client_y, minutes) => {
+            model.drag = Some(Drag {
+                kind,
+                date,
+                start_client_y: client_y,
+                start_minutes: minutes,
+                current_minutes: minutes,
+            });
+            model.drag_stream_handles = vec![
+                orders.stream_with_handle(streams::window_event(Ev::MouseMove, |event| {
+                    Msg::OnDragMove(event.unchecked_into::<web_sys::MouseEvent>().client_y())
+                })),
+                orders.stream_with_handle(streams::window_event(Ev::MouseUp, |_| Msg::OnDragEnd)),
+            ];
+        },
+        Msg::OnDragMove(client_y) => {
+            if let Some(drag) = &mut model.drag {
+                let delta_px = f64::from(client_y - drag.start_client_y);
+                drag.current_minutes = minutes_from_px(px_from_minutes(drag.start_minutes) + delta_px);
+            } else {
+                orders.skip();
+            }
+        },
+        Msg::OnDragEnd => {
+            model.drag_stream_handles.clear();
+            if let Some(drag) = model.drag.take() {
+                finish_drag(drag, model, ctx, orders);
+            }
+        },
+    }
+}
+
+fn step(mode: Mode) -> Duration {
+    match mode {
+        Mode::Day => Duration::days(1),
+        Mode::Week => Duration::weeks(1),
+    }
+}
+
+fn finish_drag(drag: Drag, model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) -> Option<()> {
+    if drag.current_minutes == drag.start_minutes {
+        return None
+    }
+    let new_time_entry_project = model.new_time_entry_project();
+    let clients = model.clients.loaded_mut()?;
+
+    let (undo, args) = match drag.kind {
+        DragKind::Started(client_id, project_id, time_entry_id) | DragKind::Stopped(client_id, project_id, time_entry_id) => {
+            let time_entry = clients
+                .get_mut(&client_id)?
+                .projects
+                .get_mut(&project_id)?
+                .time_entries
+                .get_mut(&time_entry_id)?;
+
+            let undo = Undo::SetTimeEntryTimes(client_id, project_id, time_entry_id, time_entry.started, time_entry.stopped);
+            let moved = date_time(drag.date, drag.current_minutes);
+            if let DragKind::Started(..) = drag.kind {
+                if moved >= time_entry.stopped.unwrap_or_else(Local::now) {
+                    return None
+                }
+                time_entry.started = moved;
+                if time_entry.stopped.is_none() {
+                    orders.notify(RunningTimeEntryChanged(Some(RunningTimeEntry {
+                        client_id,
+                        project_id,
+                        time_entry_id,
+                        name: time_entry.name.clone(),
+                        started: moved,
+                    })));
+                }
+            } else {
+                if moved <= time_entry.started {
+                    return None
+                }
+                time_entry.stopped = Some(moved);
+            }
+            let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+                id: time_entry_id.to_string(),
+                started: time_entry.started,
+                stopped: time_entry.stopped,
+            };
+            (undo, args)
+        },
+        DragKind::NewTimeEntry => {
+            let (client_id, project_id) = new_time_entry_project?;
+            let project = clients.get_mut(&client_id)?.projects.get_mut(&project_id)?;
+            let (started, stopped) = drag.new_time_entry_minutes();
+
+            let time_entry_id = TimeEntryId::new();
+            let time_entry = TimeEntry {
+                name: project
+                    .time_entries
+                    .values()
+                    .max_by_key(|time_entry| time_entry.started)
+                    .map(|time_entry| time_entry.name.clone())
+                    .unwrap_or_default(),
+                started: date_time(drag.date, started),
+                stopped: Some(date_time(drag.date, stopped)),
+            };
+            let args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
+                id: time_entry_id.to_string(),
+                name: time_entry.name.clone(),
+                started: time_entry.started,
+                stopped: time_entry.stopped,
+                project: project_id.to_string(),
+            };
+            let backend = ctx.backend()?;
+            model.changes_status.request_started();
+            orders.perform_cmd(async move { Msg::ChangesSaved(
+                backend.add_time_entry(args).await.err(),
+                Some(Undo::RemoveTimeEntry(client_id, project_id, time_entry_id)),
+            )});
+
+            project.time_entries.insert(time_entry_id, time_entry);
+            return Some(())
+        },
+    };
+
+    let backend = ctx.backend()?;
+    model.changes_status.request_started();
+    orders.perform_cmd(async move { Msg::ChangesSaved(
+        backend.set_time_entry_times(args).await.err(),
+        Some(undo),
+    )});
+    Some(())
+}
+
+// ------ ------
+//     View
+// ------ ------
+
+pub fn view(model: &Model, _: &Context) -> Node<Msg> {
+    section![
+        h1![C!["title", "ml-6", "mt-6", "mb-5"],
+            "Calendar",
+            common::view_changes_status(&model.changes_status),
+        ],
+        div![C!["columns", "is-centered"],
+            div![C!["column"],
+                common::view_errors(&model.errors, || Msg::ClearErrors),
+                match &model.clients {
+                    RemoteData::NotAsked => {
+                        page::log_in_required::view().into_nodes()
+                    },
+                    RemoteData::Loading => {
+                        progress![C!["progress", "is-link", "mt-6"]].into_nodes()
+                    },
+                    RemoteData::Failed => Vec::new(),
+                    RemoteData::Loaded(clients) => {
+                        nodes![
+                            view_toolbar(model, clients),
+                            view_calendar(model, clients),
+                        ]
+                    }
+                }
+            ]
+        ]
+    ]
+}
+
+fn view_toolbar(model: &Model, clients: &BTreeMap<ClientId, Client>) -> Node<Msg> {
+    let new_time_entry_project = model.new_time_entry_project();
+    div![C!["level"],
+        div![C!["level-left"],
+            div![C!["buttons", "has-addons", "level-item"],
+                button![C!["button"], ev(Ev::Click, |_| Msg::Previous), "‹"],
+                button![C!["button"], ev(Ev::Click, |_| Msg::Today), "Today"],
+                button![C!["button"], ev(Ev::Click, |_| Msg::Next), "›"],
+            ],
+            div![C!["buttons", "has-addons", "level-item"],
+                [(Mode::Day, "Day"), (Mode::Week, "Week")].iter().map(|(mode, label)| {
+                    let mode = *mode;
+                    let selected = mode == model.mode;
+                    button![C!["button", IF!(selected => "is-primary"), IF!(selected => "is-selected")],
+                        ev(Ev::Click, move |_| Msg::ModeSelected(mode)),
+                        label,
+                    ]
+                }),
+            ],
+        ],
+        div![C!["level-right"],
+            div![C!["level-item"], "Drag to add to"],
+            div![C!["select", "level-item"],
+                select![
+                    clients.iter().map(|(client_id, client)| {
+                        optgroup![
+                            attrs!{At::Label => client.name},
+                            client.projects.iter().map(|(project_id, project)| {
+                                option![
+                                    attrs!{
+                                        At::Value => format!("{}/{}", client_id, project_id),
+                                        At::Selected => (new_time_entry_project == Some((*client_id, *project_id))).as_at_value(),
+                                    },
+                                    &project.name,
+                                ]
+                            }),
+                        ]
+                    }),
+                    input_ev(Ev::Change, Msg::NewTimeEntryProjectSelected),
+                ],
+            ],
+        ],
+    ]
+}
+
+fn view_calendar(model: &Model, clients: &BTreeMap<ClientId, Client>) -> Node<Msg> {
+    let days = model.days();
+    div![C!["box"],
+        style!{St::MaxHeight => vh(70), St::OverflowY => "auto"},
+        div![C!["is-flex"],
+            view_hours(),
+            days.iter().map(|date| view_day(*date, model.drag.as_ref(), clients)),
+        ],
+    ]
+}
+
+fn view_hours() -> Node<Msg> {
+    div![
+        style!{St::Width => rem(3), St::FlexShrink => 0, St::PaddingTop => rem(2)},
+        (0..24).map(|hour| {
+            div![C!["is-size-7", "has-text-grey"],
+                style!{St::Height => px(HOUR_HEIGHT_PX)},
+                format!("{:02}:00", hour),
+            ]
+        }),
+    ]
+}
+
+fn view_day(date: NaiveDate, drag: Option<&Drag>, clients: &BTreeMap<ClientId, Client>) -> Node<Msg> {
+    let is_today = date == Local::today().naive_local();
+    div![
+        style!{St::Flex => "1 1 0", St::MinWidth => rem(6)},
+        div![C!["has-text-centered", IF!(is_today => "has-text-weight-bold")],
+            style!{St::Height => rem(2)},
+            date.format("%a %-d").to_string(),
+        ],
+        div![
+            style!{
+                St::Position => "relative",
+                St::Height => px(px_from_minutes(MINUTES_PER_DAY)),
+                St::BorderLeft => "1px solid #dbdbdb",
+                St::Cursor => "crosshair",
+                St::BackgroundImage => format!(
+                    "repeating-linear-gradient(to bottom, #f5f5f5 0, #f5f5f5 1px, transparent 1px, transparent {}px)",
+                    HOUR_HEIGHT_PX,
+                ),
+            },
+            mouse_ev(Ev::MouseDown, move |event| {
+                event.prevent_default();
+                let minutes = minutes_from_px(f64::from(event.offset_y()));
+                Msg::DragStarted(DragKind::NewTimeEntry, date, event.client_y(), minutes)
+            }),
+            clients.iter().enumerate().flat_map(|(client_index, (client_id, client))| {
+                let color = CLIENT_COLORS[client_index % CLIENT_COLORS.len()];
+                client.projects.iter().flat_map(move |(project_id, project)| {
+                    project.time_entries.iter().filter_map(move |(time_entry_id, time_entry)| {
+                        view_time_entry(date, drag, *client_id, client, *project_id, project, *time_entry_id, time_entry, color)
+                    })
+                })
+            }),
+            drag
+                .filter(|drag| drag.kind == DragKind::NewTimeEntry && drag.date == date)
+                .map(|drag| {
+                    let (started, stopped) = drag.new_time_entry_minutes();
+                    view_block(started, stopped, "#7a7a7a", nodes![], nodes![])
+                }),
+        ],
+    ]
+}
+
+#[allow(clippy::too_many_arguments)]
+fn view_time_entry(
+    date: NaiveDate,
+    drag: Option<&Drag>,
+    client_id: ClientId,
+    client: &Client,
+    project_id: ProjectId,
+    project: &Project,
+    time_entry_id: TimeEntryId,
+    time_entry: &TimeEntry,
+    color: &str,
+) -> Option<Node<Msg>> {
+    let midnight = parser::local_midnight(date);
+    let next_midnight = parser::local_midnight(date.succ());
+    let stopped = time_entry.stopped.unwrap_or_else(Local::now);
+    if time_entry.started >= next_midnight || stopped <= midnight {
+        return None
+    }
+    let mut started_minutes = (time_entry.started - midnight).num_minutes().max(0);
+    let mut stopped_minutes = (stopped - midnight).num_minutes().min(MINUTES_PER_DAY);
+
+    // Preview of the dragged edge.
+    if let Some(drag) = drag.filter(|drag| drag.date == date) {
+        match drag.kind {
+            DragKind::Started(.., id) if id == time_entry_id => started_minutes = drag.current_minutes,
+            DragKind::Stopped(.., id) if id == time_entry_id => stopped_minutes = drag.current_minutes,
+            _ => (),
+        }
+    }
+
+    let starts_today = time_entry.started >= midnight;
+    let stops_today = time_entry.stopped.map(|stopped| stopped < next_midnight).unwrap_or_default();
+
+    let handle = |kind: DragKind, minutes: i64, at_top: bool| {
+        div![
+            style!{
+                St::Position => "absolute",
+                St::Left => 0,
+                St::Right => 0,
+                if at_top { St::Top } else { St::Bottom } => 0,
+                St::Height => px(6),
+                St::Cursor => "ns-resize",
+            },
+            mouse_ev(Ev::MouseDown, move |event| {
+                event.prevent_default();
+                event.stop_propagation();
+                Msg::DragStarted(kind, date, event.client_y(), minutes)
+            }),
+        ]
+    };
+
+    Some(view_block(
+        started_minutes,
+        stopped_minutes,
+        color,
+        nodes![
+            div![C!["has-text-weight-bold"], &time_entry.name],
+            div![format!("{} · {}", client.name, project.name)],
+        ],
+        nodes![
+            IF!(starts_today => handle(DragKind::Started(client_id, project_id, time_entry_id), started_minutes, true)),
+            IF!(stops_today => handle(DragKind::Stopped(client_id, project_id, time_entry_id), stopped_minutes, false)),
+        ],
+    ))
+}
+
+fn view_block(started_minutes: i64, stopped_minutes: i64, color: &str, content: Vec<Node<Msg>>, handles: Vec<Node<Msg>>) -> Node<Msg> {
+    div![C!["is-size-7", "has-text-white"],
+        style!{
+            St::Position => "absolute",
+            St::Top => px(px_from_minutes(started_minutes)),
+            St::Height => px(px_from_minutes(stopped_minutes - started_minutes).max(2.)),
+            St::Left => px(2),
+            St::Right => px(2),
+            St::BackgroundColor => color,
+            St::BorderRadius => px(4),
+            St::Padding => "0 4px",
+            St::Overflow => "hidden",
+            St::Opacity => "0.9",
+            St::Cursor => "default",
+        },
+        // Don't start a new time entry when the block itself is clicked.
+        mouse_ev(Ev::MouseDown, |event| event.stop_propagation()),
+        content,
+        handles,
+    ]
+}
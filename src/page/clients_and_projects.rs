@@ -3,12 +3,11 @@ use seed::{prelude::*, *};
 use chrono::prelude::*;
 use ulid::Ulid;
 
-use cynic::{QueryBuilder, MutationBuilder};
-
 use std::collections::BTreeMap;
-use std::convert::identity;
+use std::mem;
 
-use crate::graphql;
+use crate::{backend::Backend, graphql, page, Context};
+use crate::page::common::{self, ChangesStatus, RemoteData};
 
 const PRIMARY_COLOR: &str = "#00d1b2";
 
@@ -19,18 +18,32 @@ type ProjectId = Ulid;
 //     Init
 // ------ ------
 
-pub fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
-    orders.perform_cmd(async { Msg::ClientsFetched(request_clients().await) });
+pub fn init(url: Url, orders: &mut impl Orders<Msg>, ctx: &Context) -> Model {
+    let clients = match (ctx.user_id(), ctx.backend()) {
+        _ if not(ctx.auth_initialized) => RemoteData::Loading,
+        (Some(user_id), Some(backend)) => {
+            orders.perform_cmd(async move { Msg::ClientsFetched(
+                request_clients(backend, user_id).await
+            )});
+            RemoteData::Loading
+        },
+        _ => RemoteData::NotAsked,
+    };
 
     Model {
         changes_status: ChangesStatus::NoChanges,
         errors: Vec::new(),
+        before_unload_handle: orders.stream_with_handle(streams::window_event(Ev::BeforeUnload, Msg::OnBeforeUnload)),
 
-        clients: RemoteData::Loading,
+        clients,
+        names_before_edit: BTreeMap::new(),
     }
 }
 
-async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
+async fn request_clients(
+    backend: Box<dyn Backend>, 
+    user_id: String,
+) -> graphql::Result<BTreeMap<ClientId, Client>> {
     use graphql::queries::clients_with_projects as query_mod;
 
     let project_mapper = |project: query_mod::Project| (
@@ -50,13 +63,12 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
         }
     );
 
+    let args = query_mod::ClientsArguments { user: user_id };
     Ok(
-        graphql::send_operation(query_mod::Query::build(()))
+        backend
+            .clients_with_projects(args)
             .await?
-            .query_client
-            .expect("get clients")
             .into_iter()
-            .filter_map(identity)
             .map(client_mapper)
             .collect()
     )
@@ -69,40 +81,11 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
 pub struct Model {
     changes_status: ChangesStatus,
     errors: Vec<graphql::GraphQLError>,
+    before_unload_handle: StreamHandle,
 
     clients: RemoteData<BTreeMap<ClientId, Client>>,
-}
-
-enum ChangesStatus {
-    NoChanges,
-    Saving { requests_in_flight: usize },
-    Saved(DateTime<Local>),
-}
-
-// ---- Remote Data ----
-
-enum RemoteData<T> {
-    NotAsked,
-    Loading,
-    Loaded(T),
-}
-
-impl<T> RemoteData<T> {
-    fn loaded(&self) -> Option<&T> {
-        if let Self::Loaded(data) = self {
-            Some(data)
-        } else {
-            None
-        }
-    }
-
-    fn loaded_mut(&mut self) -> Option<&mut T> {
-        if let Self::Loaded(data) = self {
-            Some(data)
-        } else {
-            None
-        }
-    }
+    // Saved names of the edited clients and projects, restored when the rename fails.
+    names_before_edit: BTreeMap<Ulid, String>,
 }
 
 // --- Entities ----
@@ -115,19 +98,57 @@ pub struct Client {
 }
 
 #[derive(Debug)]
-struct Project {
+pub struct Project {
     name: String,
     name_input: ElRef<web_sys::HtmlInputElement>,
 }
 
+// ---- Undo ----
+
+pub enum Undo {
+    RemoveClient(ClientId),
+    RestoreClient(ClientId, Client),
+    RemoveProject(ClientId, ProjectId),
+    RestoreProject(ClientId, ProjectId, Project),
+    RenameClient(ClientId, String),
+    RenameProject(ClientId, ProjectId, String),
+}
+
+impl common::Undo<BTreeMap<ClientId, Client>> for Undo {
+    fn apply(self, clients: &mut BTreeMap<ClientId, Client>) -> Option<()> {
+        match self {
+            Self::RemoveClient(client_id) => {
+                clients.remove(&client_id);
+            },
+            Self::RestoreClient(client_id, client) => {
+                clients.insert(client_id, client);
+            },
+            Self::RemoveProject(client_id, project_id) => {
+                clients.get_mut(&client_id)?.projects.remove(&project_id);
+            },
+            Self::RestoreProject(client_id, project_id, project) => {
+                clients.get_mut(&client_id)?.projects.insert(project_id, project);
+            },
+            Self::RenameClient(client_id, name) => {
+                clients.get_mut(&client_id)?.name = name;
+            },
+            Self::RenameProject(client_id, project_id, name) => {
+                clients.get_mut(&client_id)?.projects.get_mut(&project_id)?.name = name;
+            },
+        }
+        Some(())
+    }
+}
+
 // ------ ------
 //    Update
 // ------ ------
 
 pub enum Msg {
     ClientsFetched(graphql::Result<BTreeMap<ClientId, Client>>),
-    ChangesSaved(Option<graphql::GraphQLError>),
+    ChangesSaved(Option<graphql::GraphQLError>, Option<Undo>),
     ClearErrors,
+    OnBeforeUnload(web_sys::Event),
     
     // ------ Client ------
 
@@ -148,20 +169,23 @@ pub enum Msg {
     SaveProjectName(ClientId, ProjectId),
 }
 
-pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+pub fn update(msg: Msg, model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::ClientsFetched(Ok(clients)) => {
             model.clients = RemoteData::Loaded(clients);
+            model.names_before_edit.clear();
         },
         Msg::ClientsFetched(Err(graphql_error)) => {
+            model.clients = RemoteData::Failed;
             model.errors.push(graphql_error);
         },
 
-        Msg::ChangesSaved(None) => {
-            log!("Msg::ChangesSaved");
+        Msg::ChangesSaved(error, undo) => {
+            common::changes_saved(error, undo, &mut model.clients, &mut model.changes_status, &mut model.errors, orders);
         },
-        Msg::ChangesSaved(Some(graphql_error)) => {
-            log!("Msg::ChangesSaved", graphql_error);
+        Msg::OnBeforeUnload(event) => {
+            model.changes_status.on_before_unload(event);
+            orders.skip();
         },
 
         Msg::ClearErrors => {
@@ -171,7 +195,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         // ------ Client ------
 
         Msg::AddClient => {
-            if let Some(clients) = model.clients.loaded_mut() {
+            if let (Some(clients), Some(user_id), Some(backend)) = (model.clients.loaded_mut(), ctx.user_id(), ctx.backend()) {
                 let client_id = ClientId::new();
                 let client = Client {
                     name: "".to_owned(),
@@ -181,12 +205,12 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
                 let args = graphql::mutations::client::add::AddClientArguments {
                     id: client_id.to_string(),
-                    user: "DUMMY_USER_ID".to_owned(),
+                    user: user_id,
                 };
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::client::add::Mutation::build(&args)
-                    ).await.err()
+                    backend.add_client(args).await.err(),
+                    Some(Undo::RemoveClient(client_id)),
                 )});
 
                 clients.insert(client_id, client);
@@ -199,15 +223,17 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 let client_name = clients.get(&client_id).map(|client| &client.name)?;
 
                 if let Ok(true) = window().confirm_with_message(&format!("Client \"{}\" will be deleted.", client_name)) {
-                    clients.remove(&client_id);
+                    let client = clients.remove(&client_id)?;
+                    let undo = Undo::RestoreClient(client_id, client);
 
                     let args = graphql::mutations::client::delete::DeleteClientArguments {
                         id: client_id.to_string(),
                     };
+                    let backend = ctx.backend()?;
+                    model.changes_status.request_started();
                     orders.perform_cmd(async move { Msg::ChangesSaved(
-                        graphql::send_operation(
-                            graphql::mutations::client::delete::Mutation::build(&args)
-                        ).await.err()
+                        backend.delete_client(args).await.err(),
+                        Some(undo),
                     )});
                 }
                 Some(())
@@ -230,16 +256,16 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
         Msg::ClientNameChanged(client_id, name) => {
             let mut set_client_name = move |name| -> Option<()> {
-                Some(model
-                    .clients
-                    .loaded_mut()?
-                    .get_mut(&client_id)?
-                    .name = name)
+                let client = model.clients.loaded_mut()?.get_mut(&client_id)?;
+                let previous_name = mem::replace(&mut client.name, name);
+                model.names_before_edit.entry(client_id).or_insert(previous_name);
+                Some(())
             };
             set_client_name(name);
         },
         Msg::SaveClientName(client_id) => {
             let mut save_client_name = move |client_id| -> Option<()> {
+                let previous_name = model.names_before_edit.remove(&client_id)?;
                 let name = &model
                     .clients
                     .loaded()?
@@ -250,10 +276,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     id: client_id.to_string(),
                     name: name.clone(),
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::client::rename::Mutation::build(&args)
-                    ).await.err()
+                    backend.rename_client(args).await.err(),
+                    Some(Undo::RenameClient(client_id, previous_name)),
                 )});
                 Some(())
             };
@@ -276,10 +303,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     id: project_id.to_string(),
                     client: client_id.to_string(),
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::project::add::Mutation::build(&args)
-                    ).await.err()
+                    backend.add_project(args).await.err(),
+                    Some(Undo::RemoveProject(client_id, project_id)),
                 )});
 
                 projects.insert(project_id, project);
@@ -295,15 +323,17 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 let project_name = projects.get(&project_id).map(|project| &project.name)?;
 
                 if let Ok(true) = window().confirm_with_message(&format!("Project \"{}\" will be deleted.", project_name)) {
-                    projects.remove(&project_id);
+                    let project = projects.remove(&project_id)?;
+                    let undo = Undo::RestoreProject(client_id, project_id, project);
 
                     let args = graphql::mutations::project::delete::DeleteProjectArguments {
                         id: project_id.to_string(),
                     };
+                    let backend = ctx.backend()?;
+                    model.changes_status.request_started();
                     orders.perform_cmd(async move { Msg::ChangesSaved(
-                        graphql::send_operation(
-                            graphql::mutations::project::delete::Mutation::build(&args)
-                        ).await.err()
+                        backend.delete_project(args).await.err(),
+                        Some(undo),
                     )});
 
                 }
@@ -329,18 +359,16 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
         Msg::ProjectNameChanged(client_id, project_id, name) => {
             let mut set_project_name = move |name| -> Option<()> {
-                Some(model
-                    .clients
-                    .loaded_mut()?
-                    .get_mut(&client_id)?
-                    .projects
-                    .get_mut(&project_id)?
-                    .name = name)
+                let project = model.clients.loaded_mut()?.get_mut(&client_id)?.projects.get_mut(&project_id)?;
+                let previous_name = mem::replace(&mut project.name, name);
+                model.names_before_edit.entry(project_id).or_insert(previous_name);
+                Some(())
             };
             set_project_name(name);
         },
         Msg::SaveProjectName(client_id, project_id) => {
             let mut save_project_name = move |project_id| -> Option<()> {
+                let previous_name = model.names_before_edit.remove(&project_id)?;
                 let name = &model
                     .clients
                     .loaded()?
@@ -353,10 +381,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     id: project_id.to_string(),
                     name: name.clone(),
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::project::rename::Mutation::build(&args)
-                    ).await.err()
+                    backend.rename_project(args).await.err(),
+                    Some(Undo::RenameProject(client_id, project_id, previous_name)),
                 )});
                 Some(())
             };
@@ -369,18 +398,24 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 //     View
 // ------ ------
 
-pub fn view(model: &Model) -> Node<Msg> {
+pub fn view(model: &Model, ctx: &Context) -> Node<Msg> {
     section![
         h1![C!["title", "ml-6", "my-6"],
             "Clients & Projects",
+            common::view_changes_status(&model.changes_status),
         ],
         div![C!["columns", "is-centered"],
             div![C!["column", "is-half"],
-                view_add_client_button(),
+                common::view_errors(&model.errors, || Msg::ClearErrors),
+                IF!(model.clients.loaded().is_some() => view_add_client_button()),
                 match &model.clients {
-                    RemoteData::NotAsked | RemoteData::Loading => {
+                    RemoteData::NotAsked => {
+                        page::log_in_required::view().into_nodes()
+                    },
+                    RemoteData::Loading => {
                         progress![C!["progress", "is-link", "mt-6"]].into_nodes()
                     },
+                    RemoteData::Failed => Vec::new(),
                     RemoteData::Loaded(clients) => {
                         clients.iter().rev().map(|(client_id, client)| view_client(*client_id, client)).collect()
                     }
@@ -0,0 +1,203 @@
+use seed::{prelude::*, *};
+
+use chrono::{prelude::*, Duration};
+
+use crate::graphql;
+
+// ------ ------
+//  Remote Data
+// ------ ------
+
+pub enum RemoteData<T> {
+    NotAsked,
+    Loading,
+    Loaded(T),
+    // The error itself is displayed by `view_errors`.
+    Failed,
+}
+
+impl<T> RemoteData<T> {
+    pub fn loaded(&self) -> Option<&T> {
+        if let Self::Loaded(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
+
+    pub fn loaded_mut(&mut self) -> Option<&mut T> {
+        if let Self::Loaded(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
+}
+
+// ------ ------
+//    Changes
+// ------ ------
+
+pub enum ChangesStatus {
+    NoChanges,
+    // `saved` is the time of the last successful save - shown again when a failed request finishes.
+    Saving { requests_in_flight: usize, saved: Option<DateTime<Local>> },
+    Saved(DateTime<Local>),
+}
+
+impl ChangesStatus {
+    pub fn request_started(&mut self) {
+        *self = match *self {
+            Self::NoChanges => Self::Saving { requests_in_flight: 1, saved: None },
+            Self::Saving { requests_in_flight, saved } => Self::Saving { requests_in_flight: requests_in_flight + 1, saved },
+            Self::Saved(saved) => Self::Saving { requests_in_flight: 1, saved: Some(saved) },
+        };
+    }
+
+    pub fn request_finished(&mut self, success: bool) {
+        if let Self::Saving { requests_in_flight, saved } = *self {
+            let saved = if success { Some(Local::now()) } else { saved };
+            *self = match (requests_in_flight, saved) {
+                (1, Some(saved)) => Self::Saved(saved),
+                (1, None) => Self::NoChanges,
+                (requests_in_flight, saved) => Self::Saving { requests_in_flight: requests_in_flight - 1, saved },
+            };
+        }
+    }
+
+    // Asks the user to confirm leaving the page while some changes are still being saved.
+    pub fn on_before_unload(&self, event: web_sys::Event) {
+        if let Self::Saving { .. } = self {
+            event.prevent_default();
+            event
+                .unchecked_into::<web_sys::BeforeUnloadEvent>()
+                .set_return_value("Changes are still being saved.");
+        }
+    }
+}
+
+// Reverts an optimistic change when its mutation fails.
+pub trait Undo<T> {
+    fn apply(self, data: &mut T) -> Option<()>;
+}
+
+// Handles the result of a mutation sent after an optimistic change.
+// Queued changes are kept - they will be sent when the connection is restored.
+pub fn changes_saved<T, Ms: 'static>(
+    error: Option<graphql::GraphQLError>,
+    undo: Option<impl Undo<T>>,
+    data: &mut RemoteData<T>,
+    changes_status: &mut ChangesStatus,
+    errors: &mut Vec<graphql::GraphQLError>,
+    orders: &mut impl Orders<Ms>,
+) {
+    match error {
+        None => {
+            changes_status.request_finished(true);
+        },
+        Some(graphql::GraphQLError::Queued) => {
+            orders.notify(graphql::queue::QueueChanged);
+            changes_status.request_finished(false);
+        },
+        Some(graphql_error) => {
+            if let (Some(undo), Some(data)) = (undo, data.loaded_mut()) {
+                undo.apply(data);
+            }
+            errors.push(graphql_error);
+            changes_status.request_finished(false);
+        },
+    }
+}
+
+// ------ ------
+//     View
+// ------ ------
+
+pub fn view_changes_status<Ms>(changes_status: &ChangesStatus) -> Node<Ms> {
+    match changes_status {
+        ChangesStatus::NoChanges => empty![],
+        ChangesStatus::Saving { .. } => {
+            span![C!["tag", "is-warning", "is-medium", "ml-4"], "Saving…"]
+        },
+        ChangesStatus::Saved(saved) => {
+            span![C!["tag", "is-primary", "is-light", "is-medium", "ml-4"],
+                format!("Saved at {}", saved.format("%H:%M")),
+            ]
+        },
+    }
+}
+
+pub fn view_errors<Ms: 'static, E: ToString>(
+    errors: &[E],
+    on_clear: impl FnOnce() -> Ms + Clone + 'static,
+) -> Node<Ms> {
+    if errors.is_empty() {
+        return empty![];
+    }
+    div![C!["notification", "is-danger", "mt-6"],
+        button![C!["delete"],
+            ev(Ev::Click, move |_| on_clear()),
+        ],
+        ul![
+            errors.iter().map(|error| li![error.to_string()]),
+        ],
+    ]
+}
+
+// ------ ------
+//    Format
+// ------ ------
+
+// `H:MM:SS`, e.g. `1:05:30` or `-0:10:00`.
+pub fn format_duration(duration: &Duration) -> String {
+    let num_seconds = duration.num_seconds();
+
+    let negative = num_seconds < 0;
+    let num_seconds = num_seconds.abs();
+    let hours = num_seconds / 3600;
+    let minutes = num_seconds % 3600 / 60;
+    let seconds = num_seconds % 60;
+
+    format!("{}{}:{:02}:{:02}", if negative { "-" } else { "" }, hours, minutes, seconds)
+}
+
+// `H:MM`, e.g. `1:05` or `-0:10`.
+pub fn format_hours_and_minutes(duration: &Duration) -> String {
+    let num_minutes = duration.num_minutes();
+
+    let negative = num_minutes < 0;
+    let num_minutes = num_minutes.abs();
+
+    format!("{}{}:{:02}", if negative { "-" } else { "" }, num_minutes / 60, num_minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_request_keeps_the_last_saved_time() {
+        let mut changes_status = ChangesStatus::NoChanges;
+        changes_status.request_started();
+        changes_status.request_finished(true);
+        let saved = match changes_status {
+            ChangesStatus::Saved(saved) => saved,
+            _ => panic!("changes should be saved"),
+        };
+
+        changes_status.request_started();
+        changes_status.request_started();
+        changes_status.request_finished(false);
+        assert!(matches!(changes_status, ChangesStatus::Saving { requests_in_flight: 1, saved: Some(_) }));
+        changes_status.request_finished(false);
+        assert!(matches!(changes_status, ChangesStatus::Saved(time) if time == saved));
+    }
+
+    #[test]
+    fn failed_first_request_means_no_changes() {
+        let mut changes_status = ChangesStatus::NoChanges;
+        changes_status.request_started();
+        changes_status.request_finished(false);
+        assert!(matches!(changes_status, ChangesStatus::NoChanges));
+    }
+}
@@ -0,0 +1,718 @@
+use seed::{prelude::*, *};
+
+use chrono::prelude::*;
+use ulid::Ulid;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{backend::Backend, csv, graphql, page, parser, Context};
+use crate::page::common::{self, RemoteData};
+use graphql::mutations::{client, project, time_entry};
+
+type ClientId = Ulid;
+type ProjectId = Ulid;
+type TimeEntryId = Ulid;
+
+const PREVIEW_ROW_LIMIT: usize = 100;
+// Used for rows without a client or a project, e.g. Toggl entries without a project.
+const NO_CLIENT_NAME: &str = "No client";
+const NO_PROJECT_NAME: &str = "No project";
+
+// ------ ------
+//     Init
+// ------ ------
+
+pub fn init(_: Url, orders: &mut impl Orders<Msg>, ctx: &Context) -> Model {
+    let clients = match (ctx.user_id(), ctx.backend()) {
+        _ if not(ctx.auth_initialized) => RemoteData::Loading,
+        (Some(user_id), Some(backend)) => {
+            orders.perform_cmd(async move { Msg::ClientsFetched(
+                request_clients(backend, user_id).await
+            )});
+            RemoteData::Loading
+        },
+        _ => RemoteData::NotAsked,
+    };
+
+    Model {
+        errors: Vec::new(),
+
+        file_name: None,
+        file_error: None,
+        table: None,
+        mapping: BTreeMap::new(),
+        preview: Vec::new(),
+        import_status: ImportStatus::Idle,
+        clients,
+    }
+}
+
+async fn request_clients(
+    backend: Box<dyn Backend>,
+    user_id: String,
+) -> graphql::Result<BTreeMap<ClientId, Client>> {
+    use graphql::queries::clients_with_projects_with_time_entries as query_mod;
+
+    let project_mapper = |project: query_mod::Project| (
+        project.id.parse().expect("parse project Ulid"),
+        Project {
+            name: project.name,
+            time_entries_started: project
+                .time_entries
+                .into_iter()
+                .map(|time_entry| time_entry.started.0.parse().expect("parse time_entry started time"))
+                .collect(),
+        },
+    );
+
+    let client_mapper = |client: query_mod::Client| (
+        client.id.parse().expect("parse client Ulid"),
+        Client {
+            name: client.name,
+            projects: client.projects.into_iter().map(project_mapper).collect(),
+        }
+    );
+
+    let args = query_mod::ClientsArguments { user: user_id };
+    Ok(
+        backend
+            .clients_with_projects_with_time_entries(args)
+            .await?
+            .into_iter()
+            .map(client_mapper)
+            .collect()
+    )
+}
+
+// ------ ------
+//     Model
+// ------ ------
+
+pub struct Model {
+    errors: Vec<graphql::GraphQLError>,
+
+    file_name: Option<String>,
+    file_error: Option<String>,
+    table: Option<Table>,
+    // Fields without a column aren't in the map.
+    mapping: BTreeMap<Field, usize>,
+    // Updated by `update_preview` when the table, the mapping or the clients change.
+    preview: Vec<PreviewRow>,
+    import_status: ImportStatus,
+    clients: RemoteData<BTreeMap<ClientId, Client>>,
+}
+
+enum ImportStatus {
+    Idle,
+    Importing,
+    // Clients are reloaded after each import, `count` is `None` when the import failed.
+    Reloading { count: Option<usize> },
+    Imported { count: usize },
+}
+
+impl ImportStatus {
+    fn in_progress(&self) -> bool {
+        matches!(self, Self::Importing | Self::Reloading { .. })
+    }
+}
+
+// --- Entities ----
+
+#[derive(Debug)]
+pub struct Client {
+    name: String,
+    projects: BTreeMap<ProjectId, Project>,
+}
+
+#[derive(Debug)]
+pub struct Project {
+    name: String,
+    // Imported time entries with the same project and start are duplicates.
+    time_entries_started: Vec<DateTime<Local>>,
+}
+
+// ---- Table ----
+
+// The imported file - CSV with a header row or a JSON array of objects.
+pub struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn from_csv(text: &str) -> Result<Self, String> {
+        let mut rows = csv::read(text)?.into_iter();
+        let columns = rows.next().ok_or_else(|| "The file is empty.".to_owned())?;
+        Ok(Self { columns, rows: rows.collect() })
+    }
+
+    // Nested values are kept as JSON text.
+    fn from_json(text: &str) -> Result<Self, String> {
+        let objects = serde_json::from_str::<Vec<serde_json::Map<String, serde_json::Value>>>(text)
+            .map_err(|error| format!("The file is not a JSON array of objects: {}", error))?;
+
+        let mut columns = Vec::<String>::new();
+        for key in objects.iter().flat_map(|object| object.keys()) {
+            if not(columns.contains(key)) {
+                columns.push(key.clone());
+            }
+        }
+        let rows = objects.iter().map(|object| {
+            columns.iter().map(|column| match object.get(column) {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(serde_json::Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+            }).collect()
+        }).collect();
+        Ok(Self { columns, rows })
+    }
+}
+
+// ---- Field ----
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Field {
+    Client,
+    Project,
+    Name,
+    Started,
+    // Optional - Toggl and Clockify export dates and times in separate columns.
+    StartedTime,
+    Stopped,
+    StoppedTime,
+}
+
+impl Field {
+    const ALL: [Field; 7] = [
+        Self::Client,
+        Self::Project,
+        Self::Name,
+        Self::Started,
+        Self::StartedTime,
+        Self::Stopped,
+        Self::StoppedTime,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Client => "Client",
+            Self::Project => "Project",
+            Self::Name => "Name",
+            Self::Started => "Start",
+            Self::StartedTime => "Start time",
+            Self::Stopped => "Stop",
+            Self::StoppedTime => "Stop time",
+        }
+    }
+
+    // Lowercase column names used by Toggl, Clockify and our CSV export.
+    fn column_names(self) -> &'static [&'static str] {
+        match self {
+            Self::Client => &["client"],
+            Self::Project => &["project"],
+            Self::Name => &["description", "entry name", "name", "task"],
+            Self::Started => &["start date", "started", "start"],
+            Self::StartedTime => &["start time"],
+            Self::Stopped => &["end date", "stopped", "stop", "end"],
+            Self::StoppedTime => &["end time"],
+        }
+    }
+}
+
+fn guess_mapping(columns: &[String]) -> BTreeMap<Field, usize> {
+    Field::ALL.iter().filter_map(|field| {
+        field.column_names().iter().find_map(|name| {
+            columns
+                .iter()
+                .position(|column| column.trim().to_lowercase() == *name)
+                .map(|column| (*field, column))
+        })
+    }).collect()
+}
+
+// ---- Preview ----
+
+struct PreviewRow {
+    client: String,
+    project: String,
+    name: String,
+    times: parser::Result<(DateTime<Local>, DateTime<Local>)>,
+    // Already stored or earlier in the file.
+    duplicate: bool,
+}
+
+impl PreviewRow {
+    fn importable(&self) -> bool {
+        self.times.is_ok() && not(self.duplicate)
+    }
+}
+
+fn preview_rows(table: &Table, mapping: &BTreeMap<Field, usize>, clients: &BTreeMap<ClientId, Client>) -> Vec<PreviewRow> {
+    // (client name, project name, started timestamp)
+    let mut known_time_entries = clients.values().flat_map(|client| {
+        client.projects.values().flat_map(move |project| {
+            project.time_entries_started.iter().map(move |started| {
+                (client.name.clone(), project.name.clone(), started.timestamp())
+            })
+        })
+    }).collect::<BTreeSet<_>>();
+
+    table.rows.iter().map(|row| {
+        let value = |field: Field| {
+            mapping
+                .get(&field)
+                .and_then(|column| row.get(*column))
+                .map(|value| value.trim())
+                .unwrap_or_default()
+        };
+        let date_time = |date_field: Field, time_field: Field| -> parser::Result<DateTime<Local>> {
+            let text = format!("{} {}", value(date_field), value(time_field));
+            if text.trim().is_empty() {
+                Err(format!("{} is missing.", date_field.label()))?
+            }
+            parser::parse_date_time(&text)
+        };
+        let or_default = |value: &str, default: &str| {
+            if value.is_empty() { default.to_owned() } else { value.to_owned() }
+        };
+
+        let client = or_default(value(Field::Client), NO_CLIENT_NAME);
+        let project = or_default(value(Field::Project), NO_PROJECT_NAME);
+        let times = date_time(Field::Started, Field::StartedTime).and_then(|started| {
+            let stopped = date_time(Field::Stopped, Field::StoppedTime)?;
+            if stopped < started {
+                Err("The time entry ends before it starts.".to_owned())?
+            }
+            Ok((started, stopped))
+        });
+        let duplicate = match &times {
+            Ok((started, _)) => not(known_time_entries.insert((client.clone(), project.clone(), started.timestamp()))),
+            Err(_) => false,
+        };
+        PreviewRow {
+            client,
+            project,
+            name: value(Field::Name).to_owned(),
+            times,
+            duplicate,
+        }
+    }).collect()
+}
+
+// ------ ------
+//    Update
+// ------ ------
+
+pub enum Msg {
+    ClientsFetched(graphql::Result<BTreeMap<ClientId, Client>>),
+    ClearErrors,
+
+    FileSelected(Option<web_sys::File>),
+    // File name and its content.
+    FileRead(String, Option<String>),
+    // The `String` is a column index or empty when the field isn't mapped.
+    MappingChanged(Field, String),
+
+    Import,
+    // The number of imported time entries and whether some changes were queued.
+    Imported(graphql::Result<(usize, bool)>),
+}
+
+pub fn update(msg: Msg, model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::ClientsFetched(result) => {
+            match result {
+                Ok(clients) => model.clients = RemoteData::Loaded(clients),
+                Err(graphql_error) => {
+                    model.clients = RemoteData::Failed;
+                    model.errors.push(graphql_error);
+                },
+            }
+            if let ImportStatus::Reloading { count } = model.import_status {
+                model.import_status = count.map_or(ImportStatus::Idle, |count| ImportStatus::Imported { count });
+            }
+            update_preview(model);
+        },
+        Msg::ClearErrors => {
+            model.errors.clear();
+        },
+
+        // ------ File ------
+
+        Msg::FileSelected(Some(file)) => {
+            orders.perform_cmd(async move {
+                let text = wasm_bindgen_futures::JsFuture::from(file.text())
+                    .await
+                    .ok()
+                    .and_then(|text| text.as_string());
+                Msg::FileRead(file.name(), text)
+            });
+        },
+        Msg::FileSelected(None) => (),
+        Msg::FileRead(file_name, text) => {
+            let table = match text {
+                Some(text) if file_name.to_lowercase().ends_with(".json") => Table::from_json(&text),
+                Some(text) => Table::from_csv(&text),
+                None => Err("The file cannot be read.".to_owned()),
+            };
+            match table {
+                Ok(table) => {
+                    model.mapping = guess_mapping(&table.columns);
+                    model.table = Some(table);
+                    model.file_error = None;
+                },
+                Err(error) => {
+                    model.table = None;
+                    model.file_error = Some(error);
+                },
+            }
+            model.file_name = Some(file_name);
+            if not(model.import_status.in_progress()) {
+                model.import_status = ImportStatus::Idle;
+            }
+            update_preview(model);
+        },
+        Msg::MappingChanged(field, column) => {
+            match column.parse() {
+                Ok(column) => model.mapping.insert(field, column),
+                Err(_) => model.mapping.remove(&field),
+            };
+            update_preview(model);
+        },
+
+        // ------ Import ------
+
+        Msg::Import => {
+            let mut import = move || -> Option<()> {
+                if model.import_status.in_progress() {
+                    None?
+                }
+                let backend = ctx.backend()?;
+                let user_id = ctx.user_id()?;
+
+                let clients = model.clients.loaded_mut()?;
+                let mut client_ids = clients
+                    .iter()
+                    .map(|(client_id, client)| (client.name.clone(), *client_id))
+                    .collect::<BTreeMap<_, _>>();
+                let mut project_ids = clients
+                    .values()
+                    .flat_map(|client| client.projects.iter().map(move |(project_id, project)| {
+                        ((client.name.clone(), project.name.clone()), *project_id)
+                    }))
+                    .collect::<BTreeMap<_, _>>();
+
+                let mut new_clients = Vec::new();
+                let mut new_projects = Vec::new();
+                let mut time_entries = Vec::new();
+                let mut imported_keys = Vec::new();
+                for row in model.preview.iter().filter(|row| row.importable()) {
+                    let (started, stopped) = *row.times.as_ref().ok()?;
+                    let client_id = *client_ids.entry(row.client.clone()).or_insert_with(|| {
+                        let client_id = ClientId::new();
+                        new_clients.push((client_id, row.client.clone()));
+                        client_id
+                    });
+                    let project_id = *project_ids.entry((row.client.clone(), row.project.clone())).or_insert_with(|| {
+                        let project_id = ProjectId::new();
+                        new_projects.push((project_id, client_id, row.project.clone()));
+                        project_id
+                    });
+                    time_entries.push(time_entry::add_many::NewTimeEntry {
+                        id: TimeEntryId::new().to_string(),
+                        name: row.name.clone(),
+                        project: project_id.to_string(),
+                        started,
+                        stopped: Some(stopped),
+                    });
+                    imported_keys.push((client_id, project_id, started));
+                }
+                if time_entries.is_empty() {
+                    None?
+                }
+
+                // Imported time entries are duplicates from now on - even before the clients are reloaded.
+                for (client_id, name) in &new_clients {
+                    clients.insert(*client_id, Client { name: name.clone(), projects: BTreeMap::new() });
+                }
+                for (project_id, client_id, name) in &new_projects {
+                    clients.get_mut(client_id)?.projects.insert(*project_id, Project {
+                        name: name.clone(),
+                        time_entries_started: Vec::new(),
+                    });
+                }
+                for (client_id, project_id, started) in imported_keys {
+                    clients
+                        .get_mut(&client_id)?
+                        .projects
+                        .get_mut(&project_id)?
+                        .time_entries_started
+                        .push(started);
+                }
+                update_preview(model);
+
+                model.import_status = ImportStatus::Importing;
+                orders.perform_cmd(async move { Msg::Imported(
+                    import_time_entries(backend, user_id, new_clients, new_projects, time_entries).await
+                )});
+                Some(())
+            };
+            import();
+        },
+        Msg::Imported(result) => {
+            let count = match result {
+                Ok((count, queued)) => {
+                    if queued {
+                        orders.notify(graphql::queue::QueueChanged);
+                    }
+                    Some(count)
+                },
+                Err(graphql_error) => {
+                    model.errors.push(graphql_error);
+                    None
+                },
+            };
+            // Reload to show partially imported data and the real IDs of the imported clients and projects.
+            if let (Some(user_id), Some(backend)) = (ctx.user_id(), ctx.backend()) {
+                model.import_status = ImportStatus::Reloading { count };
+                orders.perform_cmd(async move { Msg::ClientsFetched(
+                    request_clients(backend, user_id).await
+                )});
+            } else {
+                model.import_status = count.map_or(ImportStatus::Idle, |count| ImportStatus::Imported { count });
+            }
+        },
+    }
+}
+
+// Mutations are sent one by one - projects need their client and time entries their project.
+// Queued mutations don't stop the import because the queue keeps their order.
+async fn import_time_entries(
+    backend: Box<dyn Backend>,
+    user_id: String,
+    new_clients: Vec<(ClientId, String)>,
+    new_projects: Vec<(ProjectId, ClientId, String)>,
+    time_entries: Vec<time_entry::add_many::NewTimeEntry>,
+) -> graphql::Result<(usize, bool)> {
+    let mut queued = false;
+
+    for (client_id, name) in new_clients {
+        queued |= sent_or_queued(backend.add_client(client::add::AddClientArguments {
+            id: client_id.to_string(),
+            user: user_id.clone(),
+        }).await)?;
+        queued |= sent_or_queued(backend.rename_client(client::rename::RenameClientArguments {
+            id: client_id.to_string(),
+            name,
+        }).await)?;
+    }
+
+    for (project_id, client_id, name) in new_projects {
+        queued |= sent_or_queued(backend.add_project(project::add::AddProjectArguments {
+            id: project_id.to_string(),
+            client: client_id.to_string(),
+        }).await)?;
+        queued |= sent_or_queued(backend.rename_project(project::rename::RenameProjectArguments {
+            id: project_id.to_string(),
+            name,
+        }).await)?;
+    }
+
+    let count = time_entries.len();
+    queued |= sent_or_queued(backend.add_time_entries(time_entry::add_many::AddTimeEntriesArguments {
+        time_entries,
+    }).await)?;
+
+    Ok((count, queued))
+}
+
+fn update_preview(model: &mut Model) {
+    model.preview = match (&model.table, model.clients.loaded()) {
+        (Some(table), Some(clients)) => preview_rows(table, &model.mapping, clients),
+        _ => Vec::new(),
+    };
+}
+
+fn sent_or_queued(result: graphql::Result<()>) -> graphql::Result<bool> {
+    match result {
+        Ok(()) => Ok(false),
+        Err(graphql::GraphQLError::Queued) => Ok(true),
+        Err(graphql_error) => Err(graphql_error),
+    }
+}
+
+// ------ ------
+//     View
+// ------ ------
+
+pub fn view(model: &Model, _: &Context) -> Node<Msg> {
+    section![
+        h1![C!["title", "ml-6", "mt-6", "mb-5"],
+            "Import",
+        ],
+        div![C!["columns", "is-centered"],
+            div![C!["column", "is-two-thirds"],
+                common::view_errors(&model.errors, || Msg::ClearErrors),
+                match &model.clients {
+                    RemoteData::NotAsked => {
+                        page::log_in_required::view().into_nodes()
+                    },
+                    RemoteData::Loading => {
+                        progress![C!["progress", "is-link", "mt-6"]].into_nodes()
+                    },
+                    RemoteData::Failed => Vec::new(),
+                    RemoteData::Loaded(_) => {
+                        nodes![
+                            view_file_input(model.file_name.as_deref(), model.file_error.as_deref()),
+                            model.table.as_ref().map(|table| {
+                                view_import(table, &model.mapping, &model.preview, &model.import_status)
+                            }),
+                        ]
+                    }
+                }
+            ]
+        ]
+    ]
+}
+
+fn view_file_input(file_name: Option<&str>, file_error: Option<&str>) -> Node<Msg> {
+    div![C!["box"],
+        div![C!["file", "has-name", "is-fullwidth"],
+            label![C!["file-label"],
+                input![C!["file-input"],
+                    attrs!{At::Type => "file", At::Accept => ".csv,.json"},
+                    ev(Ev::Change, |event| {
+                        Msg::FileSelected(
+                            event
+                                .target()
+                                .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+                                .and_then(|input| input.files())
+                                .and_then(|files| files.get(0))
+                        )
+                    }),
+                ],
+                span![C!["file-cta"],
+                    span![C!["file-label"], "Choose a CSV or JSON file…"],
+                ],
+                span![C!["file-name"], file_name.unwrap_or("No file selected")],
+            ],
+        ],
+        p![C!["help"],
+            "Exports from Toggl, Clockify and this app's CSV export are recognized. ",
+            "Columns can be mapped manually below.",
+        ],
+        file_error.map(|error| p![C!["help", "is-danger"], error]),
+    ]
+}
+
+fn view_import(
+    table: &Table,
+    mapping: &BTreeMap<Field, usize>,
+    rows: &[PreviewRow],
+    import_status: &ImportStatus,
+) -> Node<Msg> {
+    let importable_count = rows.iter().filter(|row| row.importable()).count();
+    let duplicate_count = rows.iter().filter(|row| row.duplicate).count();
+    let invalid_count = rows.iter().filter(|row| row.times.is_err()).count();
+    let importing = import_status.in_progress();
+
+    div![C!["box"],
+        view_mapping(&table.columns, mapping),
+        div![C!["level", "mt-5"],
+            div![C!["level-left"],
+                div![C!["level-item", "tags"],
+                    span![C!["tag", "is-primary", "is-light"], format!("{} new", importable_count)],
+                    span![C!["tag", "is-light"], format!("{} duplicates", duplicate_count)],
+                    span![C!["tag", "is-danger", "is-light"], format!("{} invalid", invalid_count)],
+                ],
+            ],
+            div![C!["level-right"],
+                button![C!["button", "is-primary", "level-item", IF!(importing => "is-loading")],
+                    attrs!{At::Disabled => (importable_count == 0 || importing).as_at_value()},
+                    ev(Ev::Click, |_| Msg::Import),
+                    format!("Import {} time entries", importable_count),
+                ],
+            ],
+        ],
+        if let ImportStatus::Imported { count } = import_status {
+            div![C!["notification", "is-primary", "is-light"],
+                format!("Imported {} time entries.", count),
+            ]
+        } else {
+            empty![]
+        },
+        view_preview(rows),
+    ]
+}
+
+fn view_mapping(columns: &[String], mapping: &BTreeMap<Field, usize>) -> Node<Msg> {
+    div![C!["columns", "is-multiline"],
+        Field::ALL.iter().map(|field| {
+            let field = *field;
+            let selected_column = mapping.get(&field).copied();
+            div![C!["column", "is-one-quarter"],
+                div![C!["field"],
+                    label![C!["label"], field.label()],
+                    div![C!["control"],
+                        div![C!["select", "is-fullwidth"],
+                            select![
+                                option![attrs!{At::Value => ""}, "—"],
+                                columns.iter().enumerate().map(|(index, column)| {
+                                    option![
+                                        attrs!{
+                                            At::Value => index,
+                                            At::Selected => (selected_column == Some(index)).as_at_value(),
+                                        },
+                                        column,
+                                    ]
+                                }),
+                                input_ev(Ev::Change, move |column| Msg::MappingChanged(field, column)),
+                            ],
+                        ],
+                    ],
+                ],
+            ]
+        }),
+    ]
+}
+
+fn view_preview(rows: &[PreviewRow]) -> Node<Msg> {
+    let date_time_format = "%Y-%m-%d %H:%M";
+    div![C!["table-container"],
+        table![C!["table", "is-fullwidth", "is-narrow", "is-hoverable"],
+            thead![
+                tr![
+                    th!["Client"], th!["Project"], th!["Name"], th!["Start"], th!["Stop"], th!["Status"],
+                ],
+            ],
+            tbody![
+                rows.iter().take(PREVIEW_ROW_LIMIT).map(|row| {
+                    let (started, stopped) = match &row.times {
+                        Ok((started, stopped)) => (
+                            started.format(date_time_format).to_string(),
+                            stopped.format(date_time_format).to_string(),
+                        ),
+                        Err(_) => (String::new(), String::new()),
+                    };
+                    tr![C![IF!(not(row.importable()) => "has-text-grey-light")],
+                        td![&row.client],
+                        td![&row.project],
+                        td![&row.name],
+                        td![started],
+                        td![stopped],
+                        td![
+                            match &row.times {
+                                Err(error) => span![C!["has-text-danger"], error],
+                                Ok(_) if row.duplicate => span!["Duplicate"],
+                                Ok(_) => span![C!["has-text-primary"], "New"],
+                            }
+                        ],
+                    ]
+                }),
+            ],
+        ],
+        IF!(rows.len() > PREVIEW_ROW_LIMIT => {
+            p![C!["help"], format!("… and {} more rows.", rows.len() - PREVIEW_ROW_LIMIT)]
+        }),
+    ]
+}
@@ -0,0 +1,7 @@
+use seed::{prelude::*, *};
+
+pub fn view<Ms>() -> Node<Ms> {
+    div![C!["notification", "is-link", "is-light", "mt-6", "has-text-centered", "is-size-5"],
+        "Please log in to see your clients, projects and tracked time.",
+    ]
+}
@@ -0,0 +1,448 @@
+use seed::{prelude::*, *};
+
+use chrono::{prelude::*, Duration};
+use ulid::Ulid;
+
+use std::collections::BTreeMap;
+use std::ops::Add;
+
+use crate::{backend::Backend, graphql, page, parser, Context};
+use crate::page::common::{self, RemoteData};
+
+type ClientId = Ulid;
+type ProjectId = Ulid;
+type TimeEntryName = String;
+
+// ------ ------
+//     Init
+// ------ ------
+
+pub fn init(_: Url, orders: &mut impl Orders<Msg>, ctx: &Context) -> Model {
+    let range = Preset::ThisWeek.range(Local::today().naive_local());
+    let mut model = Model {
+        errors: Vec::new(),
+
+        preset: Preset::ThisWeek,
+        range,
+        custom_from: range.from.format("%F").to_string(),
+        custom_to: range.to.format("%F").to_string(),
+        custom_range_error: None,
+
+        clients: RemoteData::NotAsked,
+    };
+    fetch_clients(&mut model, orders, ctx);
+    model
+}
+
+fn fetch_clients(model: &mut Model, orders: &mut impl Orders<Msg>, ctx: &Context) {
+    model.clients = match (ctx.user_id(), ctx.backend()) {
+        _ if not(ctx.auth_initialized) => RemoteData::Loading,
+        (Some(user_id), Some(backend)) => {
+            let range = model.range;
+            orders.perform_cmd(async move { Msg::ClientsFetched(
+                range,
+                request_clients(backend, user_id, range).await
+            )});
+            RemoteData::Loading
+        },
+        _ => RemoteData::NotAsked,
+    };
+}
+
+async fn request_clients(
+    backend: Box<dyn Backend>,
+    user_id: String,
+    range: DateRange,
+) -> graphql::Result<BTreeMap<ClientId, Client>> {
+    use graphql::queries::clients_with_projects_with_time_entries_in_range as query_mod;
+
+    let time_entry_mapper = |time_entry: query_mod::TimeEntry| {
+        let started: DateTime<Local> =
+            time_entry.started.0.parse().expect("parse time_entry started");
+
+        let stopped: DateTime<Local> = if let Some(stopped) = time_entry.stopped {
+            stopped.0.parse().expect("parse time_entry stopped")
+        } else {
+            chrono::Local::now()
+        };
+
+        (time_entry.name, stopped - started)
+    };
+
+    let project_mapper = |project: query_mod::Project| {
+        let mut time_entries = BTreeMap::<TimeEntryName, Duration>::new();
+        for (name, duration) in project.time_entries.into_iter().map(time_entry_mapper) {
+            let total = time_entries.entry(name).or_insert_with(Duration::zero);
+            *total = *total + duration;
+        }
+        (
+            project.id.parse().expect("parse project Ulid"),
+            Project {
+                name: project.name,
+                time_entries,
+            },
+        )
+    };
+
+    let client_mapper = |client: query_mod::Client| (
+        client.id.parse().expect("parse client Ulid"),
+        Client {
+            name: client.name,
+            projects: client.projects.into_iter().map(project_mapper).collect(),
+        }
+    );
+
+    let args = query_mod::ClientsArguments {
+        user: user_id,
+        started_from: parser::local_midnight(range.from),
+        started_to: parser::local_midnight(range.to.succ()),
+    };
+    Ok(
+        backend
+            .clients_with_projects_with_time_entries_in_range(args)
+            .await?
+            .into_iter()
+            .map(client_mapper)
+            .collect()
+    )
+}
+
+// ------ ------
+//     Model
+// ------ ------
+
+pub struct Model {
+    errors: Vec<graphql::GraphQLError>,
+
+    preset: Preset,
+    range: DateRange,
+    custom_from: String,
+    custom_to: String,
+    custom_range_error: Option<String>,
+
+    clients: RemoteData<BTreeMap<ClientId, Client>>,
+}
+
+// ---- Date Range ----
+
+// Both dates are included.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DateRange {
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Preset {
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    LastMonth,
+    Custom,
+}
+
+impl Preset {
+    const ALL: [Self; 5] = [Self::ThisWeek, Self::LastWeek, Self::ThisMonth, Self::LastMonth, Self::Custom];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::ThisWeek => "This week",
+            Self::LastWeek => "Last week",
+            Self::ThisMonth => "This month",
+            Self::LastMonth => "Last month",
+            Self::Custom => "Custom",
+        }
+    }
+
+    // `Custom` falls back to this week.
+    fn range(self, today: NaiveDate) -> DateRange {
+        let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+        let first_day_of_month = today.with_day(1).expect("first day of month");
+        match self {
+            Self::ThisWeek | Self::Custom => DateRange {
+                from: monday,
+                to: monday + Duration::days(6),
+            },
+            Self::LastWeek => DateRange {
+                from: monday - Duration::days(7),
+                to: monday - Duration::days(1),
+            },
+            Self::ThisMonth => DateRange {
+                from: first_day_of_month,
+                to: (first_day_of_month + Duration::days(31)).with_day(1).expect("first day of next month").pred(),
+            },
+            Self::LastMonth => DateRange {
+                from: first_day_of_month.pred().with_day(1).expect("first day of last month"),
+                to: first_day_of_month.pred(),
+            },
+        }
+    }
+}
+
+// --- Entities ----
+
+#[derive(Debug)]
+pub struct Client {
+    name: String,
+    projects: BTreeMap<ProjectId, Project>,
+}
+
+impl Client {
+    fn total(&self) -> Duration {
+        self.projects.values().map(Project::total).fold(Duration::zero(), Duration::add)
+    }
+}
+
+#[derive(Debug)]
+pub struct Project {
+    name: String,
+    // Durations of all time entries with the same name are summed.
+    time_entries: BTreeMap<TimeEntryName, Duration>,
+}
+
+impl Project {
+    fn total(&self) -> Duration {
+        self.time_entries.values().cloned().fold(Duration::zero(), Duration::add)
+    }
+}
+
+// ------ ------
+//    Update
+// ------ ------
+
+pub enum Msg {
+    ClientsFetched(DateRange, graphql::Result<BTreeMap<ClientId, Client>>),
+    ClearErrors,
+
+    PresetSelected(Preset),
+    CustomFromChanged(String),
+    CustomToChanged(String),
+    ApplyCustomRange,
+}
+
+pub fn update(msg: Msg, model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::ClientsFetched(range, Ok(clients)) => {
+            // Ignore responses for ranges that are no longer selected.
+            if range == model.range {
+                model.clients = RemoteData::Loaded(clients);
+            }
+        },
+        Msg::ClientsFetched(range, Err(graphql_error)) => {
+            if range == model.range {
+                model.clients = RemoteData::Failed;
+            }
+            model.errors.push(graphql_error);
+        },
+
+        Msg::ClearErrors => {
+            model.errors.clear();
+        },
+
+        Msg::PresetSelected(preset) => {
+            model.preset = preset;
+            if preset == Preset::Custom {
+                return
+            }
+            model.range = preset.range(Local::today().naive_local());
+            model.custom_from = model.range.from.format("%F").to_string();
+            model.custom_to = model.range.to.format("%F").to_string();
+            model.custom_range_error = None;
+            fetch_clients(model, orders, ctx);
+        },
+        Msg::CustomFromChanged(from) => {
+            model.custom_from = from;
+        },
+        Msg::CustomToChanged(to) => {
+            model.custom_to = to;
+        },
+        Msg::ApplyCustomRange => {
+            let range = parser::parse_date(&model.custom_from).and_then(|from| {
+                let to = parser::parse_date(&model.custom_to)?;
+                if to < from {
+                    Err("The end date has to be the same as or after the start date.")?
+                }
+                Ok(DateRange { from, to })
+            });
+            match range {
+                Ok(range) => {
+                    model.range = range;
+                    model.custom_range_error = None;
+                    fetch_clients(model, orders, ctx);
+                },
+                Err(error) => model.custom_range_error = Some(error),
+            }
+        },
+    }
+}
+
+// ------ ------
+//     View
+// ------ ------
+
+pub fn view(model: &Model, _: &Context) -> Node<Msg> {
+    section![
+        h1![C!["title", "ml-6", "mt-6", "mb-5"],
+            "Reports",
+        ],
+        div![C!["columns", "is-centered"],
+            div![C!["column", "is-two-thirds"],
+                common::view_errors(&model.errors, || Msg::ClearErrors),
+                view_range_picker(model),
+                match &model.clients {
+                    RemoteData::NotAsked => {
+                        page::log_in_required::view().into_nodes()
+                    },
+                    RemoteData::Loading => {
+                        progress![C!["progress", "is-link", "mt-6"]].into_nodes()
+                    },
+                    RemoteData::Failed => Vec::new(),
+                    RemoteData::Loaded(clients) => {
+                        view_report(model.range, clients)
+                    }
+                }
+            ]
+        ]
+    ]
+}
+
+fn view_range_picker(model: &Model) -> Node<Msg> {
+    div![C!["box"],
+        div![C!["buttons", "has-addons"],
+            Preset::ALL.iter().map(|preset| {
+                let preset = *preset;
+                let selected = preset == model.preset;
+                button![C!["button", IF!(selected => "is-primary"), IF!(selected => "is-selected")],
+                    ev(Ev::Click, move |_| Msg::PresetSelected(preset)),
+                    preset.label(),
+                ]
+            }),
+        ],
+        IF!(model.preset == Preset::Custom => div![C!["field", "has-addons"],
+            div![C!["control"],
+                input![C!["input"],
+                    attrs!{At::Value => model.custom_from, At::Placeholder => "From, e.g. 2020-01-13 or mon"},
+                    input_ev(Ev::Input, Msg::CustomFromChanged),
+                    ev(Ev::Change, |_| Msg::ApplyCustomRange),
+                ],
+            ],
+            div![C!["control"],
+                input![C!["input"],
+                    attrs!{At::Value => model.custom_to, At::Placeholder => "To, e.g. 2020-01-19 or today"},
+                    input_ev(Ev::Input, Msg::CustomToChanged),
+                    ev(Ev::Change, |_| Msg::ApplyCustomRange),
+                ],
+            ],
+            div![C!["control"],
+                button![C!["button", "is-primary"],
+                    ev(Ev::Click, |_| Msg::ApplyCustomRange),
+                    "Show",
+                ],
+            ],
+        ]),
+        model.custom_range_error.as_ref().map(|error| {
+            p![C!["help", "is-danger"], error]
+        }),
+    ]
+}
+
+fn view_report(range: DateRange, clients: &BTreeMap<ClientId, Client>) -> Vec<Node<Msg>> {
+    let total = clients.values().map(Client::total).fold(Duration::zero(), Duration::add);
+    nodes![
+        div![C!["level", "is-mobile", "mt-5"],
+            div![C!["is-size-5"],
+                format!("{} – {}", range.from.format("%F"), range.to.format("%F")),
+            ],
+            div![C!["is-size-4", "has-text-weight-bold"],
+                common::format_hours_and_minutes(&total),
+            ],
+        ],
+        IF!(total.is_zero() => div![C!["notification", "is-light"], "No time entries in this range."]),
+        clients
+            .values()
+            .filter(|client| not(client.total().is_zero()))
+            .map(view_client),
+    ]
+}
+
+fn view_client(client: &Client) -> Node<Msg> {
+    div![C!["box", "has-background-link", "mt-5"],
+        div![C!["level", "is-mobile", "has-text-link-light"],
+            div![C!["is-size-3"], &client.name],
+            div![C!["is-size-4"], common::format_hours_and_minutes(&client.total())],
+        ],
+        client
+            .projects
+            .values()
+            .filter(|project| not(project.total().is_zero()))
+            .map(view_project),
+    ]
+}
+
+fn view_project(project: &Project) -> Node<Msg> {
+    div![C!["box"],
+        div![C!["level", "is-mobile"],
+            div![C!["is-size-4"], &project.name],
+            div![C!["is-size-5", "has-text-weight-bold"], common::format_hours_and_minutes(&project.total())],
+        ],
+        table![C!["table", "is-fullwidth", "is-striped"],
+            tbody![
+                project.time_entries.iter().map(|(name, duration)| {
+                    tr![
+                        td![if name.is_empty() { "(no name)" } else { name.as_str() }],
+                        td![C!["has-text-right"], common::format_hours_and_minutes(duration)],
+                    ]
+                }),
+            ],
+        ],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    fn range(preset: Preset, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let range = preset.range(today);
+        (range.from, range.to)
+    }
+
+    #[test]
+    fn this_month_ends_on_the_last_day_of_month() {
+        assert_eq!(range(Preset::ThisMonth, date(2021, 1, 31)), (date(2021, 1, 1), date(2021, 1, 31)));
+        assert_eq!(range(Preset::ThisMonth, date(2021, 2, 1)), (date(2021, 2, 1), date(2021, 2, 28)));
+        assert_eq!(range(Preset::ThisMonth, date(2020, 2, 29)), (date(2020, 2, 1), date(2020, 2, 29)));
+        assert_eq!(range(Preset::ThisMonth, date(2021, 4, 30)), (date(2021, 4, 1), date(2021, 4, 30)));
+        assert_eq!(range(Preset::ThisMonth, date(2021, 12, 31)), (date(2021, 12, 1), date(2021, 12, 31)));
+    }
+
+    #[test]
+    fn last_month_in_january_is_december_of_last_year() {
+        assert_eq!(range(Preset::LastMonth, date(2021, 1, 1)), (date(2020, 12, 1), date(2020, 12, 31)));
+        assert_eq!(range(Preset::LastMonth, date(2021, 1, 31)), (date(2020, 12, 1), date(2020, 12, 31)));
+    }
+
+    #[test]
+    fn last_month_ends_on_the_last_day_of_month() {
+        assert_eq!(range(Preset::LastMonth, date(2021, 3, 31)), (date(2021, 2, 1), date(2021, 2, 28)));
+        assert_eq!(range(Preset::LastMonth, date(2020, 3, 1)), (date(2020, 2, 1), date(2020, 2, 29)));
+    }
+
+    #[test]
+    fn sunday_belongs_to_the_week_starting_on_monday() {
+        // 2021-01-03 is a Sunday.
+        assert_eq!(range(Preset::ThisWeek, date(2021, 1, 3)), (date(2020, 12, 28), date(2021, 1, 3)));
+        assert_eq!(range(Preset::LastWeek, date(2021, 1, 3)), (date(2020, 12, 21), date(2020, 12, 27)));
+        assert_eq!(range(Preset::Custom, date(2021, 1, 3)), range(Preset::ThisWeek, date(2021, 1, 3)));
+    }
+
+    #[test]
+    fn monday_starts_the_week() {
+        assert_eq!(range(Preset::ThisWeek, date(2021, 1, 4)), (date(2021, 1, 4), date(2021, 1, 10)));
+        assert_eq!(range(Preset::LastWeek, date(2021, 1, 4)), (date(2020, 12, 28), date(2021, 1, 3)));
+    }
+}
@@ -1,11 +1,13 @@
 use seed::{prelude::*, *};
 use chrono::prelude::*;
 
+use crate::Context;
+
 // ------ ------
 //     Init
 // ------ ------
 
-pub fn init(url: Url, _: &mut impl Orders<Msg>) -> Model {
+pub fn init(url: Url, _: &mut impl Orders<Msg>, ctx: &Context) -> Model {
     Model {
         changes_status: ChangesStatus::NoChanges,
         errors: Vec::new(),
@@ -72,7 +74,7 @@ pub enum Msg {
     DeleteAccount,
 }
 
-pub fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+pub fn update(msg: Msg, model: &mut Model, ctx: &Context, _: &mut impl Orders<Msg>) {
     match msg {
         Msg::ChangesSaved(None) => {},
         Msg::ChangesSaved(Some(fetch_error)) => {},
@@ -92,6 +94,6 @@ pub fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
 //     View
 // ------ ------
 
-pub fn view(model: &Model) -> Node<Msg> {
+pub fn view(model: &Model, ctx: &Context) -> Node<Msg> {
     div!["Settings view"]
 }
@@ -3,13 +3,13 @@ use seed::{prelude::*, *};
 use chrono::{prelude::*, Duration};
 use ulid::Ulid;
 
-use cynic::{QueryBuilder, MutationBuilder};
-
 use std::collections::BTreeMap;
-use std::convert::identity;
+use std::convert::TryFrom;
+use std::mem;
 use std::ops::Add;
 
-use crate::graphql;
+use crate::{backend::Backend, graphql, page, parser, Context};
+use crate::page::common::{self, ChangesStatus, RemoteData};
 
 const PRIMARY_COLOR: &str = "#00d1b2";
 
@@ -21,18 +21,32 @@ type TimeBlockId = Ulid;
 //     Init
 // ------ ------
 
-pub fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
-    orders.perform_cmd(async { Msg::ClientsFetched(request_clients().await) });
+pub fn init(url: Url, orders: &mut impl Orders<Msg>, ctx: &Context) -> Model {
+    let clients = match (ctx.user_id(), ctx.backend()) {
+        _ if not(ctx.auth_initialized) => RemoteData::Loading,
+        (Some(user_id), Some(backend)) => {
+            orders.perform_cmd(async move { Msg::ClientsFetched(
+                request_clients(backend, user_id).await
+            )});
+            RemoteData::Loading
+        },
+        _ => RemoteData::NotAsked,
+    };
 
     Model {
         changes_status: ChangesStatus::NoChanges,
         errors: Vec::new(),
+        before_unload_handle: orders.stream_with_handle(streams::window_event(Ev::BeforeUnload, Msg::OnBeforeUnload)),
 
-        clients: RemoteData::Loading,
+        clients,
+        values_before_edit: BTreeMap::new(),
     }
 }
 
-async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
+async fn request_clients(
+    backend: Box<dyn Backend>, 
+    user_id: String,
+) -> graphql::Result<BTreeMap<ClientId, Client>> {
     use graphql::queries::clients_with_time_blocks_and_time_entries as query_mod;
 
     let invoice_mapper = |invoice: query_mod::Invoice| {
@@ -58,6 +72,7 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
             status: status_mapper(time_block.status),
             duration: Duration::seconds(i64::from(time_block.duration)),
             duration_change: None,
+            duration_error: None,
             invoice: time_block.invoice.map(invoice_mapper),
             name_input: ElRef::new(),
         }
@@ -91,13 +106,12 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
         }
     );
 
+    let args = query_mod::ClientsArguments { user: user_id };
     Ok(
-        graphql::send_operation(query_mod::Query::build(&()))
+        backend
+            .clients_with_time_blocks_and_time_entries(args)
             .await?
-            .query_client
-            .expect("get clients")
             .into_iter()
-            .filter_map(identity)
             .map(client_mapper)
             .collect()
     )
@@ -110,40 +124,18 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
 pub struct Model {
     changes_status: ChangesStatus,
     errors: Vec<graphql::GraphQLError>,
+    before_unload_handle: StreamHandle,
 
     clients: RemoteData<BTreeMap<ClientId, Client>>,
+    // Saved values of the edited fields, restored when the change fails.
+    values_before_edit: BTreeMap<(TimeBlockId, EditedField), Option<String>>,
 }
 
-enum ChangesStatus {
-    NoChanges,
-    Saving { requests_in_flight: usize },
-    Saved(DateTime<Local>),
-}
-
-// ---- Remote Data ----
-
-enum RemoteData<T> {
-    NotAsked,
-    Loading,
-    Loaded(T),
-}
-
-impl<T> RemoteData<T> {
-    fn loaded(&mut self) -> Option<&T> {
-        if let Self::Loaded(data) = self {
-            Some(data)
-        } else {
-            None
-        }
-    }
-
-    fn loaded_mut(&mut self) -> Option<&mut T> {
-        if let Self::Loaded(data) = self {
-            Some(data)
-        } else {
-            None
-        }
-    }
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum EditedField {
+    TimeBlockName,
+    InvoiceCustomId,
+    InvoiceUrl,
 }
 
 // --- Entities ----
@@ -156,11 +148,12 @@ pub struct Client {
 }
 
 #[derive(Debug)]
-struct TimeBlock {
+pub struct TimeBlock {
     name: String,
     status: TimeBlockStatus,
     duration: Duration,
     duration_change: Option<String>,
+    duration_error: Option<String>,
     invoice: Option<Invoice>,
     name_input: ElRef<web_sys::HtmlInputElement>,
 }
@@ -173,20 +166,70 @@ pub enum TimeBlockStatus {
 }
 
 #[derive(Debug)]
-struct Invoice {
+pub struct Invoice {
     id: InvoiceId,
     custom_id: Option<String>,
     url: Option<String>,
 }
 
+// ---- Undo ----
+
+pub enum Undo {
+    RemoveTimeBlock(ClientId, TimeBlockId),
+    RestoreTimeBlock(ClientId, TimeBlockId, TimeBlock),
+    SetTimeBlockStatus(ClientId, TimeBlockId, TimeBlockStatus),
+    SetTimeBlockDuration(ClientId, TimeBlockId, Duration),
+    RemoveInvoice(ClientId, TimeBlockId),
+    RestoreInvoice(ClientId, TimeBlockId, Invoice),
+    RenameTimeBlock(ClientId, TimeBlockId, String),
+    SetInvoiceCustomId(ClientId, TimeBlockId, Option<String>),
+    SetInvoiceUrl(ClientId, TimeBlockId, Option<String>),
+}
+
+impl common::Undo<BTreeMap<ClientId, Client>> for Undo {
+    fn apply(self, clients: &mut BTreeMap<ClientId, Client>) -> Option<()> {
+        match self {
+            Self::RemoveTimeBlock(client_id, time_block_id) => {
+                clients.get_mut(&client_id)?.time_blocks.remove(&time_block_id);
+            },
+            Self::RestoreTimeBlock(client_id, time_block_id, time_block) => {
+                clients.get_mut(&client_id)?.time_blocks.insert(time_block_id, time_block);
+            },
+            Self::SetTimeBlockStatus(client_id, time_block_id, status) => {
+                clients.get_mut(&client_id)?.time_blocks.get_mut(&time_block_id)?.status = status;
+            },
+            Self::SetTimeBlockDuration(client_id, time_block_id, duration) => {
+                clients.get_mut(&client_id)?.time_blocks.get_mut(&time_block_id)?.duration = duration;
+            },
+            Self::RemoveInvoice(client_id, time_block_id) => {
+                clients.get_mut(&client_id)?.time_blocks.get_mut(&time_block_id)?.invoice = None;
+            },
+            Self::RestoreInvoice(client_id, time_block_id, invoice) => {
+                clients.get_mut(&client_id)?.time_blocks.get_mut(&time_block_id)?.invoice = Some(invoice);
+            },
+            Self::RenameTimeBlock(client_id, time_block_id, name) => {
+                clients.get_mut(&client_id)?.time_blocks.get_mut(&time_block_id)?.name = name;
+            },
+            Self::SetInvoiceCustomId(client_id, time_block_id, custom_id) => {
+                clients.get_mut(&client_id)?.time_blocks.get_mut(&time_block_id)?.invoice.as_mut()?.custom_id = custom_id;
+            },
+            Self::SetInvoiceUrl(client_id, time_block_id, url) => {
+                clients.get_mut(&client_id)?.time_blocks.get_mut(&time_block_id)?.invoice.as_mut()?.url = url;
+            },
+        }
+        Some(())
+    }
+}
+
 // ------ ------
 //    Update
 // ------ ------
 
 pub enum Msg {
     ClientsFetched(graphql::Result<BTreeMap<ClientId, Client>>),
-    ChangesSaved(Option<graphql::GraphQLError>),
+    ChangesSaved(Option<graphql::GraphQLError>, Option<Undo>),
     ClearErrors,
+    OnBeforeUnload(web_sys::Event),
 
     // ------ TimeBlock ------
     
@@ -213,20 +256,23 @@ pub enum Msg {
     SaveInvoiceUrl(ClientId, TimeBlockId),
 }
 
-pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+pub fn update(msg: Msg, model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::ClientsFetched(Ok(clients)) => {
             model.clients = RemoteData::Loaded(clients);
+            model.values_before_edit.clear();
         },
         Msg::ClientsFetched(Err(graphql_error)) => {
+            model.clients = RemoteData::Failed;
             model.errors.push(graphql_error);
         },
 
-        Msg::ChangesSaved(None) => {
-            log!("Msg::ChangesSaved");
+        Msg::ChangesSaved(error, undo) => {
+            common::changes_saved(error, undo, &mut model.clients, &mut model.changes_status, &mut model.errors, orders);
         },
-        Msg::ChangesSaved(Some(fetch_error)) => {
-            log!("Msg::ChangesSaved", fetch_error);
+        Msg::OnBeforeUnload(event) => {
+            model.changes_status.on_before_unload(event);
+            orders.skip();
         },
 
         Msg::ClearErrors => {
@@ -250,6 +296,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     status: TimeBlockStatus::Unpaid,
                     duration: previous_duration.unwrap_or_else(|| chrono::Duration::hours(20)),
                     duration_change: None,
+                    duration_error: None,
                     invoice: None,
                     name_input: ElRef::new(),
                 };
@@ -259,10 +306,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     duration: time_block.duration,
                     client: client_id.to_string(),
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::time_block::add::Mutation::build(&args)
-                    ).await.err()
+                    backend.add_time_block(args).await.err(),
+                    Some(Undo::RemoveTimeBlock(client_id, time_block_id)),
                 )});
 
                 time_blocks.insert(time_block_id, time_block);
@@ -278,15 +326,17 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 let time_block_name = time_blocks.get(&time_block_id).map(|time_block| &time_block.name)?;
 
                 if let Ok(true) = window().confirm_with_message(&format!("Time Block \"{}\" will be deleted.", time_block_name)) {
-                    time_blocks.remove(&time_block_id);
+                    let time_block = time_blocks.remove(&time_block_id)?;
+                    let undo = Undo::RestoreTimeBlock(client_id, time_block_id, time_block);
 
                     let args = graphql::mutations::time_block::delete::DeleteTimeBlockArguments {
                         id: time_block_id.to_string(),
                     };
+                    let backend = ctx.backend()?;
+                    model.changes_status.request_started();
                     orders.perform_cmd(async move { Msg::ChangesSaved(
-                        graphql::send_operation(
-                            graphql::mutations::time_block::delete::Mutation::build(&args)
-                        ).await.err()
+                        backend.delete_time_block(args).await.err(),
+                        Some(undo),
                     )});
                 }
                 Some(())
@@ -295,13 +345,15 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         },
         Msg::SetTimeBlockStatus(client_id, time_block_id, time_block_status) => {
             let mut set_time_block_status = move |status| -> Option<()> {
-                model
+                let time_block = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .time_blocks
-                    .get_mut(&time_block_id)?
-                    .status = status;
+                    .get_mut(&time_block_id)?;
+
+                let undo = Undo::SetTimeBlockStatus(client_id, time_block_id, time_block.status);
+                time_block.status = status;
 
                     let args = graphql::mutations::time_block::set_status::SetTimeBlockStatusArguments {
                         id: time_block_id.to_string(),
@@ -317,10 +369,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                             }
                         }
                     };
+                    let backend = ctx.backend()?;
+                    model.changes_status.request_started();
                     orders.perform_cmd(async move { Msg::ChangesSaved(
-                        graphql::send_operation(
-                            graphql::mutations::time_block::set_status::Mutation::build(&args)
-                        ).await.err()
+                        backend.set_time_block_status(args).await.err(),
+                        Some(undo),
                     )});
 
                 Some(())
@@ -345,18 +398,16 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
         Msg::TimeBlockNameChanged(client_id, time_block_id, name) => {
             let mut set_time_block_name = move |name| -> Option<()> {
-                Some(model
-                    .clients
-                    .loaded_mut()?
-                    .get_mut(&client_id)?
-                    .time_blocks
-                    .get_mut(&time_block_id)?
-                    .name = name)
+                let time_block = model.clients.loaded_mut()?.get_mut(&client_id)?.time_blocks.get_mut(&time_block_id)?;
+                let previous_name = mem::replace(&mut time_block.name, name);
+                model.values_before_edit.entry((time_block_id, EditedField::TimeBlockName)).or_insert(Some(previous_name));
+                Some(())
             };
             set_time_block_name(name);
         },
         Msg::SaveTimeBlockName(client_id, time_block_id) => {
             let mut save_time_block_name = move |time_block_id| -> Option<()> {
+                let previous_name = model.values_before_edit.remove(&(time_block_id, EditedField::TimeBlockName))??;
                 let name = &model
                     .clients
                     .loaded()?
@@ -369,10 +420,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     id: time_block_id.to_string(),
                     name: name.clone(),
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::time_block::rename::Mutation::build(&args)
-                    ).await.err()
+                    backend.rename_time_block(args).await.err(),
+                    Some(Undo::RenameTimeBlock(client_id, time_block_id, previous_name)),
                 )});
                 Some(())
             };
@@ -380,14 +432,16 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         },
 
         Msg::TimeBlockDurationChanged(client_id, time_block_id, duration) => {
-            let mut set_time_block_duration_change = move |duration| -> Option<()> {
-                Some(model
+            let mut set_time_block_duration_change = move |duration: String| -> Option<()> {
+                let time_block = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .time_blocks
-                    .get_mut(&time_block_id)?
-                    .duration_change = Some(duration))
+                    .get_mut(&time_block_id)?;
+                time_block.duration_error = parser::parse_duration(&duration).and_then(validate_duration).err();
+                time_block.duration_change = Some(duration);
+                Some(())
             };
             set_time_block_duration_change(duration);
         },
@@ -400,17 +454,27 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     .time_blocks
                     .get_mut(&time_block_id)?;
 
-                let hours = time_block.duration_change.take()?.parse::<f64>().ok()?;
-                time_block.duration = chrono::Duration::seconds((hours * 3600.0) as i64);
+                let seconds = match parser::parse_duration(time_block.duration_change.as_ref()?).and_then(validate_duration) {
+                    Ok(seconds) => seconds,
+                    Err(error) => {
+                        time_block.duration_error = Some(error);
+                        return None
+                    }
+                };
+                time_block.duration_change = None;
+                time_block.duration_error = None;
+                let undo = Undo::SetTimeBlockDuration(client_id, time_block_id, time_block.duration);
+                time_block.duration = Duration::seconds(i64::from(seconds));
 
                 let args = graphql::mutations::time_block::set_duration::SetTimeBlockDurationArguments {
                     id: time_block_id.to_string(),
-                    duration: time_block.duration.num_seconds() as i32,
+                    duration: seconds,
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::time_block::set_duration::Mutation::build(&args)
-                    ).await.err()
+                    backend.set_time_block_duration(args).await.err(),
+                    Some(undo),
                 )});
 
                 Some(())
@@ -440,10 +504,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     id: invoice_id.to_string(),
                     time_block: time_block_id.to_string(),
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::invoice::add::Mutation::build(&args)
-                    ).await.err()
+                    backend.add_invoice(args).await.err(),
+                    Some(Undo::RemoveInvoice(client_id, time_block_id)),
                 )});
 
                 time_block.invoice = Some(invoice);
@@ -465,10 +530,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                         let args = graphql::mutations::invoice::delete::DeleteInvoiceArguments {
                             id: invoice.id.to_string(),
                         };
+                        let backend = ctx.backend()?;
+                        model.changes_status.request_started();
                         orders.perform_cmd(async move { Msg::ChangesSaved(
-                            graphql::send_operation(
-                                graphql::mutations::invoice::delete::Mutation::build(&args)
-                            ).await.err()
+                            backend.delete_invoice(args).await.err(),
+                            Some(Undo::RestoreInvoice(client_id, time_block_id, invoice)),
                         )});
                     }
                 }
@@ -479,19 +545,22 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
         Msg::InvoiceCustomIdChanged(client_id, time_block_id, custom_id) => {
             let mut set_invoice_custom_id = move |client_id, time_block_id, custom_id| -> Option<()> {
-                Some(model
+                let invoice = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .time_blocks
                     .get_mut(&time_block_id)?
-                    .invoice.as_mut()?
-                    .custom_id = Some(custom_id))
+                    .invoice.as_mut()?;
+                let previous_custom_id = mem::replace(&mut invoice.custom_id, Some(custom_id));
+                model.values_before_edit.entry((time_block_id, EditedField::InvoiceCustomId)).or_insert(previous_custom_id);
+                Some(())
             };
             set_invoice_custom_id(client_id, time_block_id, custom_id);
         },
         Msg::SaveInvoiceCustomId(client_id, time_block_id) => {
             let mut save_invoice_custom_id = move |time_block_id| -> Option<()> {
+                let previous_custom_id = model.values_before_edit.remove(&(time_block_id, EditedField::InvoiceCustomId))?;
                 let invoice = model
                     .clients
                     .loaded()?
@@ -506,10 +575,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                         id: invoice.id.to_string(),
                         custom_id: custom_id.clone(),
                     };
+                    let backend = ctx.backend()?;
+                    model.changes_status.request_started();
                     orders.perform_cmd(async move { Msg::ChangesSaved(
-                        graphql::send_operation(
-                            graphql::mutations::invoice::set_custom_id::Mutation::build(&args)
-                        ).await.err()
+                        backend.set_invoice_custom_id(args).await.err(),
+                        Some(Undo::SetInvoiceCustomId(client_id, time_block_id, previous_custom_id)),
                     )});
                 }
                 Some(())
@@ -519,19 +589,22 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
         Msg::InvoiceUrlChanged(client_id, time_block_id, url) => {
             let mut set_invoice_url = move |client_id, time_block_id, url| -> Option<()> {
-                Some(model
+                let invoice = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .time_blocks
                     .get_mut(&time_block_id)?
-                    .invoice.as_mut()?
-                    .url = Some(url))
+                    .invoice.as_mut()?;
+                let previous_url = mem::replace(&mut invoice.url, Some(url));
+                model.values_before_edit.entry((time_block_id, EditedField::InvoiceUrl)).or_insert(previous_url);
+                Some(())
             };
             set_invoice_url(client_id, time_block_id, url);
         },
         Msg::SaveInvoiceUrl(client_id, time_block_id) => {
             let mut save_invoice_url = move |time_block_id| -> Option<()> {
+                let previous_url = model.values_before_edit.remove(&(time_block_id, EditedField::InvoiceUrl))?;
                 let invoice = model
                     .clients
                     .loaded()?
@@ -546,10 +619,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                         id: invoice.id.to_string(),
                         url: url.clone(),
                     };
+                    let backend = ctx.backend()?;
+                    model.changes_status.request_started();
                     orders.perform_cmd(async move { Msg::ChangesSaved(
-                        graphql::send_operation(
-                            graphql::mutations::invoice::set_url::Mutation::build(&args)
-                        ).await.err()
+                        backend.set_invoice_url(args).await.err(),
+                        Some(Undo::SetInvoiceUrl(client_id, time_block_id, previous_url)),
                     )});
                 }
                 Some(())
@@ -559,21 +633,35 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     }
 }
 
+// The duration is stored in seconds as GraphQL `Int`.
+fn validate_duration(duration: Duration) -> parser::Result<i32> {
+    if duration < Duration::zero() {
+        Err("The duration can't be negative.")?
+    }
+    i32::try_from(duration.num_seconds()).map_err(|_| "The duration is too long.".to_owned())
+}
+
 // ------ ------
 //     View
 // ------ ------
 
-pub fn view(model: &Model) -> Node<Msg> {
+pub fn view(model: &Model, ctx: &Context) -> Node<Msg> {
     section![
         h1![C!["title", "ml-6", "mt-6", "mb-5"],
             "Time Blocks",
+            common::view_changes_status(&model.changes_status),
         ],
         div![C!["columns", "is-centered"],
             div![C!["column", "is-two-thirds"],
+                common::view_errors(&model.errors, || Msg::ClearErrors),
                 match &model.clients {
-                    RemoteData::NotAsked | RemoteData::Loading => {
+                    RemoteData::NotAsked => {
+                        page::log_in_required::view().into_nodes()
+                    },
+                    RemoteData::Loading => {
                         progress![C!["progress", "is-link", "mt-6"]].into_nodes()
                     },
+                    RemoteData::Failed => Vec::new(),
                     RemoteData::Loaded(clients) => {
                         clients.iter().rev().map(|(client_id, client)| view_client(*client_id, client)).collect()
                     }
@@ -704,6 +792,9 @@ fn view_time_block(client_id: ClientId, time_block_id: TimeBlockId, time_block:
             ],
             view_delete_button(move || Msg::DeleteTimeBlock(client_id, time_block_id)),
         ],
+        time_block.duration_error.as_ref().map(|error| {
+            p![C!["help", "is-danger", "has-text-right"], error]
+        }),
         div![C!["level", "is-mobile"],
             view_status_buttons(client_id, time_block_id, time_block.status),
             IF!(time_block.invoice.is_none() => view_attach_invoice_button(client_id, time_block_id)),
@@ -2,17 +2,19 @@ use seed::{prelude::*, *};
 
 use chrono::prelude::*;
 use ulid::Ulid;
-
-use cynic::{QueryBuilder, MutationBuilder};
+use serde::{Serialize, Deserialize};
 
 use std::collections::BTreeMap;
-use std::convert::identity;
+use std::mem;
 
-use crate::graphql;
+use crate::{backend::Backend, csv, graphql, page, parser, Context, RunningTimeEntry, RunningTimeEntryChanged};
+use crate::page::common::{self, ChangesStatus, RemoteData};
 
 const PRIMARY_COLOR: &str = "#00d1b2";
 const LINK_COLOR: &str = "#3273dc";
 
+const RUNNING_TIME_ENTRIES_STORAGE_KEY: &str = "time_tracker-running_time_entries";
+
 type ClientId = Ulid;
 type ProjectId = Ulid;
 type TimeEntryId = Ulid;
@@ -21,19 +23,34 @@ type TimeEntryId = Ulid;
 //     Init
 // ------ ------
 
-pub fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
-    orders.perform_cmd(async { Msg::ClientsFetched(request_clients().await) });
+pub fn init(url: Url, orders: &mut impl Orders<Msg>, ctx: &Context) -> Model {
+    let clients = match (ctx.user_id(), ctx.backend()) {
+        _ if not(ctx.auth_initialized) => RemoteData::Loading,
+        (Some(user_id), Some(backend)) => {
+            orders.perform_cmd(async move { Msg::ClientsFetched(
+                request_clients(backend, user_id).await
+            )});
+            RemoteData::Loading
+        },
+        _ => RemoteData::NotAsked,
+    };
 
     Model {
         changes_status: ChangesStatus::NoChanges,
         errors: Vec::new(),
+        before_unload_handle: orders.stream_with_handle(streams::window_event(Ev::BeforeUnload, Msg::OnBeforeUnload)),
 
-        clients: RemoteData::Loading,
+        clients,
+        names_before_edit: BTreeMap::new(),
         timer_handle: orders.stream_with_handle(streams::interval(1000, || Msg::OnSecondTick)),
+        export: None,
     }
 }
 
-async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
+async fn request_clients(
+    backend: Box<dyn Backend>, 
+    user_id: String,
+) -> graphql::Result<BTreeMap<ClientId, Client>> {
     use graphql::queries::clients_with_projects_with_time_entries as query_mod;
 
     let time_entry_mapper = |time_entry: query_mod::TimeEntry| (
@@ -43,6 +60,8 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
             started: time_entry.started.0.parse().expect("parse time_entry started time"),
             stopped: time_entry.stopped.map(|time| time.0.parse().expect("parse time_entry started time")),
             change: None,
+            change_error: None,
+            split_time: None,
         }
     );
 
@@ -50,7 +69,8 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
         project.id.parse().expect("parse project Ulid"), 
         Project { 
             name: project.name, 
-            time_entries: project.time_entries.into_iter().map(time_entry_mapper).collect()
+            time_entries: project.time_entries.into_iter().map(time_entry_mapper).collect(),
+            new_time_entry: None,
         },
     );
 
@@ -62,13 +82,12 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
         }
     );
 
+    let args = query_mod::ClientsArguments { user: user_id };
     Ok(
-        graphql::send_operation(query_mod::Query::build(&()))
+        backend
+            .clients_with_projects_with_time_entries(args)
             .await?
-            .query_client
-            .expect("get clients")
             .into_iter()
-            .filter_map(identity)
             .map(client_mapper)
             .collect()
     )
@@ -81,41 +100,13 @@ async fn request_clients() -> graphql::Result<BTreeMap<ClientId, Client>> {
 pub struct Model {
     changes_status: ChangesStatus,
     errors: Vec<graphql::GraphQLError>,
+    before_unload_handle: StreamHandle,
 
     clients: RemoteData<BTreeMap<ClientId, Client>>,
+    // Saved names of the edited time entries, restored when the rename fails.
+    names_before_edit: BTreeMap<TimeEntryId, String>,
     timer_handle: StreamHandle, 
-}
-
-enum ChangesStatus {
-    NoChanges,
-    Saving { requests_in_flight: usize },
-    Saved(DateTime<Local>),
-}
-
-// ---- Remote Data ----
-
-enum RemoteData<T> {
-    NotAsked,
-    Loading,
-    Loaded(T),
-}
-
-impl<T> RemoteData<T> {
-    fn loaded(&self) -> Option<&T> {
-        if let Self::Loaded(data) = self {
-            Some(data)
-        } else {
-            None
-        }
-    }
-
-    fn loaded_mut(&mut self) -> Option<&mut T> {
-        if let Self::Loaded(data) = self {
-            Some(data)
-        } else {
-            None
-        }
-    }
+    export: Option<Export>,
 }
 
 // --- Entities ----
@@ -127,17 +118,32 @@ pub struct Client {
 }
 
 #[derive(Debug)]
-struct Project {
+pub struct Project {
     name: String,
     time_entries: BTreeMap<Ulid, TimeEntry>,
+    new_time_entry: Option<NewTimeEntry>,
 }
 
 #[derive(Debug)]
-struct TimeEntry {
+pub struct TimeEntry {
     name: String,
     started: DateTime<Local>,
     stopped: Option<DateTime<Local>>,
     change: Option<TimeEntryChange>,
+    change_error: Option<String>,
+    // `Some` while the split form is open.
+    split_time: Option<String>,
+}
+
+// Form for adding a past time entry. Either `stopped` or `duration` has to be filled.
+#[derive(Debug)]
+struct NewTimeEntry {
+    name: String,
+    date: String,
+    started: String,
+    stopped: String,
+    duration: String,
+    error: Option<String>,
 }
 
 #[derive(Debug)]
@@ -149,17 +155,207 @@ enum TimeEntryChange {
     Duration(String),
 }
 
+// Form for the CSV export. Empty dates don't limit the range, `client` `None` means all clients.
+#[derive(Debug, Default)]
+struct Export {
+    from: String,
+    to: String,
+    client: Option<ClientId>,
+    error: Option<String>,
+}
+
+// ---- Undo ----
+
+pub enum Undo {
+    RemoveTimeEntry(ClientId, ProjectId, TimeEntryId),
+    RestoreTimeEntry(ClientId, ProjectId, TimeEntryId, TimeEntry),
+    SetTimeEntryTimes(ClientId, ProjectId, TimeEntryId, DateTime<Local>, Option<DateTime<Local>>),
+    // From (client, project) to (client, project).
+    MoveTimeEntry(ClientId, ProjectId, ClientId, ProjectId, TimeEntryId),
+    RenameTimeEntry(ClientId, ProjectId, TimeEntryId, String),
+}
+
+impl common::Undo<BTreeMap<ClientId, Client>> for Undo {
+    fn apply(self, clients: &mut BTreeMap<ClientId, Client>) -> Option<()> {
+        match self {
+            Self::RemoveTimeEntry(client_id, project_id, time_entry_id) => {
+                clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .remove(&time_entry_id);
+            },
+            Self::RestoreTimeEntry(client_id, project_id, time_entry_id, time_entry) => {
+                clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .insert(time_entry_id, time_entry);
+            },
+            Self::SetTimeEntryTimes(client_id, project_id, time_entry_id, started, stopped) => {
+                let time_entry = clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .get_mut(&time_entry_id)?;
+
+                time_entry.started = started;
+                time_entry.stopped = stopped;
+            },
+            Self::MoveTimeEntry(from_client_id, from_project_id, to_client_id, to_project_id, time_entry_id) => {
+                move_time_entry(clients, from_client_id, from_project_id, to_client_id, to_project_id, time_entry_id)?;
+            },
+            Self::RenameTimeEntry(client_id, project_id, time_entry_id, name) => {
+                clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .get_mut(&time_entry_id)?
+                    .name = name;
+            },
+        }
+        Some(())
+    }
+}
+
+fn move_time_entry(
+    clients: &mut BTreeMap<ClientId, Client>,
+    from_client_id: ClientId,
+    from_project_id: ProjectId,
+    to_client_id: ClientId,
+    to_project_id: ProjectId,
+    time_entry_id: TimeEntryId,
+) -> Option<()> {
+    // Make sure the target exists before the time entry is removed.
+    clients.get(&to_client_id)?.projects.get(&to_project_id)?;
+    let time_entry = clients
+        .get_mut(&from_client_id)?
+        .projects
+        .get_mut(&from_project_id)?
+        .time_entries
+        .remove(&time_entry_id)?;
+    clients
+        .get_mut(&to_client_id)?
+        .projects
+        .get_mut(&to_project_id)?
+        .time_entries
+        .insert(time_entry_id, time_entry);
+    Some(())
+}
+
+// ---- Running Time Entries ----
+
+// Running time entries are mirrored to LocalStorage so they survive reloads while their mutations are queued.
+#[derive(Serialize, Deserialize)]
+struct StoredTimeEntry {
+    client_id: String,
+    project_id: String,
+    time_entry_id: String,
+    name: String,
+    started: String,
+}
+
+fn load_running_time_entries() -> Vec<StoredTimeEntry> {
+    LocalStorage::get(RUNNING_TIME_ENTRIES_STORAGE_KEY).unwrap_or_default()
+}
+
+fn save_running_time_entries(clients: &BTreeMap<ClientId, Client>) {
+    // Entries of clients that aren't loaded (e.g. another user's) are kept.
+    let mut stored_time_entries: Vec<_> = load_running_time_entries()
+        .into_iter()
+        .filter(|stored_time_entry| {
+            stored_time_entry
+                .client_id
+                .parse()
+                .map(|client_id| not(clients.contains_key(&client_id)))
+                .unwrap_or_default()
+        })
+        .collect();
+
+    for (client_id, client) in clients {
+        for (project_id, project) in &client.projects {
+            for (time_entry_id, time_entry) in &project.time_entries {
+                if time_entry.stopped.is_none() {
+                    stored_time_entries.push(StoredTimeEntry {
+                        client_id: client_id.to_string(),
+                        project_id: project_id.to_string(),
+                        time_entry_id: time_entry_id.to_string(),
+                        name: time_entry.name.clone(),
+                        started: time_entry.started.to_rfc3339(),
+                    });
+                }
+            }
+        }
+    }
+    if let Err(error) = LocalStorage::insert(RUNNING_TIME_ENTRIES_STORAGE_KEY, &stored_time_entries) {
+        error!("Cannot save running time entries!", error);
+    }
+}
+
+// Shows a stored running time entry unknown to the server while its addition is still queued.
+// Other unknown entries have been stopped or deleted elsewhere, or their addition failed -
+// they are dropped from the storage by `save_running_time_entries` at the end of `update`.
+// Nothing is restored when the server already has a running time entry.
+fn restore_running_time_entry(clients: &mut BTreeMap<ClientId, Client>) -> Option<()> {
+    let server_has_running_time_entry = clients
+        .values()
+        .flat_map(|client| client.projects.values())
+        .flat_map(|project| project.time_entries.values())
+        .any(|time_entry| time_entry.stopped.is_none());
+    if server_has_running_time_entry {
+        None?
+    }
+
+    let (client_id, project_id, time_entry_id, name, started) = load_running_time_entries()
+        .into_iter()
+        .filter(|stored_time_entry| graphql::queue::refers_to(&stored_time_entry.time_entry_id))
+        .filter_map(|stored_time_entry| {
+            let client_id: ClientId = stored_time_entry.client_id.parse().ok()?;
+            let project_id: ProjectId = stored_time_entry.project_id.parse().ok()?;
+            let time_entry_id: TimeEntryId = stored_time_entry.time_entry_id.parse().ok()?;
+            let started = DateTime::parse_from_rfc3339(&stored_time_entry.started).ok()?.with_timezone(&Local);
+            clients.get(&client_id)?.projects.get(&project_id)?;
+            Some((client_id, project_id, time_entry_id, stored_time_entry.name, started))
+        })
+        // Only one time entry can be running.
+        .max_by_key(|(_, _, _, _, started)| *started)?;
+
+    clients
+        .get_mut(&client_id)?
+        .projects
+        .get_mut(&project_id)?
+        .time_entries
+        .insert(time_entry_id, TimeEntry {
+            name,
+            started,
+            stopped: None,
+            change: None,
+            change_error: None,
+            split_time: None,
+        });
+    Some(())
+}
+
 // ------ ------
 //    Update
 // ------ ------
 
 pub enum Msg {
     ClientsFetched(graphql::Result<BTreeMap<ClientId, Client>>),
-    ChangesSaved(Option<graphql::GraphQLError>),
+    ChangesSaved(Option<graphql::GraphQLError>, Option<Undo>),
     ClearErrors,
+    OnBeforeUnload(web_sys::Event),
     
     Start(ClientId, ProjectId),
+    Continue(ClientId, ProjectId, TimeEntryId),
     Stop(ClientId, ProjectId),
+    StopAt(ClientId, ProjectId, DateTime<Local>),
+    // The `DateTime` is the start of the idle time.
+    DiscardIdleTime(ClientId, ProjectId, DateTime<Local>),
 
     DeleteTimeEntry(ClientId, ProjectId, TimeEntryId),
     
@@ -176,23 +372,70 @@ pub enum Msg {
 
     SaveTimeEntryChange(ClientId, ProjectId, TimeEntryId),
 
+    ToggleNewTimeEntryForm(ClientId, ProjectId),
+    NewTimeEntryNameChanged(ClientId, ProjectId, String),
+    NewTimeEntryDateChanged(ClientId, ProjectId, String),
+    NewTimeEntryStartedChanged(ClientId, ProjectId, String),
+    NewTimeEntryStoppedChanged(ClientId, ProjectId, String),
+    NewTimeEntryDurationChanged(ClientId, ProjectId, String),
+    AddTimeEntry(ClientId, ProjectId),
+
+    ToggleSplitTimeEntryForm(ClientId, ProjectId, TimeEntryId),
+    SplitTimeChanged(ClientId, ProjectId, TimeEntryId, String),
+    SplitTimeEntry(ClientId, ProjectId, TimeEntryId),
+    MergeWithPreviousTimeEntry(ClientId, ProjectId, TimeEntryId),
+    // The `String` is the target in the format `[client id]/[project id]`.
+    MoveTimeEntry(ClientId, ProjectId, TimeEntryId, String),
+
+    TrimOverlappedTimeEntry(TimeEntryId),
+    ShiftOverlappingTimeEntry(ClientId, ProjectId, TimeEntryId),
+
+    ToggleExportForm,
+    ExportFromChanged(String),
+    ExportToChanged(String),
+    // The `String` is a client id or empty for all clients.
+    ExportClientSelected(String),
+    Export,
+
     OnSecondTick,
 }
 
-pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+pub fn update(msg: Msg, model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) {
+    let running_time_entries_changed = matches!(msg, 
+        Msg::ClientsFetched(Ok(_)) 
+        | Msg::ChangesSaved(Some(_), Some(_))
+        | Msg::Start(..) 
+        | Msg::Continue(..) 
+        | Msg::Stop(..) 
+        | Msg::StopAt(..) 
+        | Msg::DiscardIdleTime(..)
+        | Msg::DeleteTimeEntry(..) 
+        | Msg::SaveTimeEntryName(..) 
+        | Msg::SaveTimeEntryChange(..)
+        | Msg::SplitTimeEntry(..)
+        | Msg::MergeWithPreviousTimeEntry(..)
+        | Msg::MoveTimeEntry(..)
+        | Msg::TrimOverlappedTimeEntry(..)
+        | Msg::ShiftOverlappingTimeEntry(..)
+    );
+
     match msg {
-        Msg::ClientsFetched(Ok(clients)) => {
+        Msg::ClientsFetched(Ok(mut clients)) => {
+            restore_running_time_entry(&mut clients);
             model.clients = RemoteData::Loaded(clients);
+            model.names_before_edit.clear();
         },
         Msg::ClientsFetched(Err(graphql_error)) => {
+            model.clients = RemoteData::Failed;
             model.errors.push(graphql_error);
         },
 
-        Msg::ChangesSaved(None) => {
-            log!("Msg::ChangesSaved");
+        Msg::ChangesSaved(error, undo) => {
+            common::changes_saved(error, undo, &mut model.clients, &mut model.changes_status, &mut model.errors, orders);
         },
-        Msg::ChangesSaved(Some(graphql_error)) => {
-            log!("Msg::ChangesSaved", graphql_error);
+        Msg::OnBeforeUnload(event) => {
+            model.changes_status.on_before_unload(event);
+            orders.skip();
         },
 
         Msg::ClearErrors => {
@@ -200,74 +443,28 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         },
 
         Msg::Start(client_id, project_id) => {
-            let mut start_time_entry = move |client_id, project_id| -> Option<()> {
-                let time_entries = &mut model
-                    .clients
-                    .loaded_mut()?
-                    .get_mut(&client_id)?
-                    .projects
-                    .get_mut(&project_id)?
-                    .time_entries;
-
-                let previous_name = time_entries
-                    .iter()
-                    .next_back()
-                    .map(|(_, time_entry)| time_entry.name.to_owned());
-
-                let time_entry_id = TimeEntryId::new();
-                let time_entry = TimeEntry {
-                    name: previous_name.unwrap_or_default(),
-                    started: chrono::Local::now(),
-                    stopped: None,
-                    change: None,
-                };
-                
-                let args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
-                    id: time_entry_id.to_string(),
-                    name: time_entry.name.clone(),
-                    started: time_entry.started.clone(),
-                    project: project_id.to_string(),
-                };
-                orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::time_entry::add::Mutation::build(&args)
-                    ).await.err()
-                )});
-
-                time_entries.insert(time_entry_id, time_entry);
-
-                Some(())
-            };
-            start_time_entry(client_id, project_id);
+            stop_running_time_entries(model, ctx, orders);
+            start_time_entry(model, ctx, orders, client_id, project_id, None);
+        },
+        Msg::Continue(client_id, project_id, time_entry_id) => {
+            stop_running_time_entries(model, ctx, orders);
+            let name = model
+                .clients
+                .loaded()
+                .and_then(|clients| clients.get(&client_id)?.projects.get(&project_id)?.time_entries.get(&time_entry_id))
+                .map(|time_entry| time_entry.name.clone());
+            if name.is_some() {
+                start_time_entry(model, ctx, orders, client_id, project_id, name);
+            }
         },
         Msg::Stop(client_id, project_id) => {
-            let mut stop_time_entry = move |client_id, project_id| -> Option<()> {
-                let (time_entry_id, time_entry) = model
-                    .clients
-                    .loaded_mut()?
-                    .get_mut(&client_id)?
-                    .projects
-                    .get_mut(&project_id)?
-                    .time_entries
-                    .iter_mut()
-                    .find(|(_, time_entry)| time_entry.stopped.is_none())?;
-                
-                time_entry.stopped = Some(chrono::Local::now());
-
-                let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
-                    id: time_entry_id.to_string(),
-                    started: time_entry.started.clone(),
-                    stopped: time_entry.stopped.clone(),
-                };
-                orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::time_entry::set_times::Mutation::build(&args)
-                    ).await.err()
-                )});
-
-                Some(())
-            };
-            stop_time_entry(client_id, project_id);
+            stop_time_entry(model, ctx, orders, client_id, project_id, chrono::Local::now());
+        },
+        Msg::StopAt(client_id, project_id, stopped) => {
+            stop_time_entry(model, ctx, orders, client_id, project_id, stopped);
+        },
+        Msg::DiscardIdleTime(client_id, project_id, idle_since) => {
+            discard_idle_time(model, ctx, orders, client_id, project_id, idle_since);
         },
 
         Msg::DeleteTimeEntry(client_id, project_id, time_entry_id) => {
@@ -283,15 +480,17 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 let time_entry_name = &time_entries.get_mut(&time_entry_id)?.name;
 
                 if let Ok(true) = window().confirm_with_message(&format!("Time Entry \"{}\" will be deleted.", time_entry_name)) {
-                    time_entries.remove(&time_entry_id);
+                    let time_entry = time_entries.remove(&time_entry_id)?;
+                    let undo = Undo::RestoreTimeEntry(client_id, project_id, time_entry_id, time_entry);
 
                     let args = graphql::mutations::time_entry::delete::DeleteTimeEntryArguments {
                         id: time_entry_id.to_string(),
                     };
+                    let backend = ctx.backend()?;
+                    model.changes_status.request_started();
                     orders.perform_cmd(async move { Msg::ChangesSaved(
-                        graphql::send_operation(
-                            graphql::mutations::time_entry::delete::Mutation::build(&args)
-                        ).await.err()
+                        backend.delete_time_entry(args).await.err(),
+                        Some(undo),
                     )});
                 }
                 Some(())
@@ -301,20 +500,23 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
         Msg::TimeEntryNameChanged(client_id, project_id, time_entry_id, name) => {
             let mut set_time_entry_name = move |name| -> Option<()> {
-                Some(model
+                let time_entry = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .projects
                     .get_mut(&project_id)?
                     .time_entries
-                    .get_mut(&time_entry_id)?
-                    .name = name)
+                    .get_mut(&time_entry_id)?;
+                let previous_name = mem::replace(&mut time_entry.name, name);
+                model.names_before_edit.entry(time_entry_id).or_insert(previous_name);
+                Some(())
             };
             set_time_entry_name(name);
         },
         Msg::SaveTimeEntryName(client_id, project_id, time_entry_id) => {
             let mut save_time_entry_name = move |time_entry_id| -> Option<()> {
+                let previous_name = model.names_before_edit.remove(&time_entry_id)?;
                 let name = &model
                     .clients
                     .loaded()?
@@ -329,10 +531,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     id: time_entry_id.to_string(),
                     name: name.clone(),
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::time_entry::rename::Mutation::build(&args)
-                    ).await.err()
+                    backend.rename_time_entry(args).await.err(),
+                    Some(Undo::RenameTimeEntry(client_id, project_id, time_entry_id, previous_name)),
                 )});
                 Some(())
             };
@@ -341,73 +544,83 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
         Msg::TimeEntryStartedDateChanged(client_id, project_id, time_entry_id, date) => {
             let mut set_time_entry_change = move |change| -> Option<()> {
-                Some(model
+                let time_entry = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .projects
                     .get_mut(&project_id)?
                     .time_entries
-                    .get_mut(&time_entry_id)?
-                    .change = Some(change))
+                    .get_mut(&time_entry_id)?;
+                time_entry.change = Some(change);
+                time_entry.change_error = None;
+                Some(())
             };
             set_time_entry_change(TimeEntryChange::StartedDate(date));
         },
         Msg::TimeEntryStartedTimeChanged(client_id, project_id, time_entry_id, time) => {
             let mut set_time_entry_change = move |change| -> Option<()> {
-                Some(model
+                let time_entry = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .projects
                     .get_mut(&project_id)?
                     .time_entries
-                    .get_mut(&time_entry_id)?
-                    .change = Some(change))
+                    .get_mut(&time_entry_id)?;
+                time_entry.change = Some(change);
+                time_entry.change_error = None;
+                Some(())
             };
             set_time_entry_change(TimeEntryChange::StartedTime(time));
         },
 
         Msg::TimeEntryDurationChanged(client_id, project_id, time_entry_id, duration) => {
             let mut set_time_entry_change = move |change| -> Option<()> {
-                Some(model
+                let time_entry = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .projects
                     .get_mut(&project_id)?
                     .time_entries
-                    .get_mut(&time_entry_id)?
-                    .change = Some(change))
+                    .get_mut(&time_entry_id)?;
+                time_entry.change = Some(change);
+                time_entry.change_error = None;
+                Some(())
             };
             set_time_entry_change(TimeEntryChange::Duration(duration));
         },
 
         Msg::TimeEntryStoppedDateChanged(client_id, project_id, time_entry_id, date) => {
             let mut set_time_entry_change = move |change| -> Option<()> {
-                Some(model
+                let time_entry = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .projects
                     .get_mut(&project_id)?
                     .time_entries
-                    .get_mut(&time_entry_id)?
-                    .change = Some(change))
+                    .get_mut(&time_entry_id)?;
+                time_entry.change = Some(change);
+                time_entry.change_error = None;
+                Some(())
             };
             set_time_entry_change(TimeEntryChange::StoppedDate(date));
         },
         Msg::TimeEntryStoppedTimeChanged(client_id, project_id, time_entry_id, time) => {
             let mut set_time_entry_change = move |change| -> Option<()> {
-                Some(model
+                let time_entry = model
                     .clients
                     .loaded_mut()?
                     .get_mut(&client_id)?
                     .projects
                     .get_mut(&project_id)?
                     .time_entries
-                    .get_mut(&time_entry_id)?
-                    .change = Some(change))
+                    .get_mut(&time_entry_id)?;
+                time_entry.change = Some(change);
+                time_entry.change_error = None;
+                Some(())
             };
             set_time_entry_change(TimeEntryChange::StoppedTime(time));
         },
@@ -423,81 +636,900 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     .time_entries
                     .get_mut(&time_entry_id)?;
 
-                match time_entry.change.take()? {
-                    TimeEntryChange::StartedDate(date) => {
-                        let date = chrono::NaiveDate::parse_from_str(&date, "%F").ok()?;
-                        let time = time_entry.started.time();
-                        time_entry.started = Local.from_local_date(&date).and_time(time).single()?;
-                    }
-                    TimeEntryChange::StartedTime(time) => {
-                        let time = chrono::NaiveTime::parse_from_str(&time, "%X").ok()?;
-                        let date = time_entry.started.naive_local().date();
-                        time_entry.started = Local.from_local_date(&date).and_time(time).single()?;
-                    }
-                    TimeEntryChange::Duration(mut duration) => {
-                        let negative = duration.chars().next()? == '-';
-                        if negative {
-                            duration.remove(0);
-                        }
-                        let mut duration_parts = duration.split(':');
-                        let hours: i64 = duration_parts.next()?.parse().ok()?;
-                        let minutes: i64 = duration_parts.next()?.parse().ok()?;
-                        let seconds: i64 = duration_parts.next()?.parse().ok()?;
-
-                        let mut total_seconds = hours * 3600 + minutes * 60 + seconds;
-                        if negative {
-                            total_seconds = -total_seconds;
-                        }
-                        let duration = chrono::Duration::seconds(total_seconds);
-                        time_entry.stopped = Some(time_entry.started + duration);
-                    }
-                    TimeEntryChange::StoppedDate(date) => {
-                        let date = chrono::NaiveDate::parse_from_str(&date, "%F").ok()?;
-                        let time = time_entry.stopped?.time();
-                        time_entry.stopped = Some(Local.from_local_date(&date).and_time(time).single()?);
-                    }
-                    TimeEntryChange::StoppedTime(time) => {
-                        let time = chrono::NaiveTime::parse_from_str(&time, "%X").ok()?;
-                        let date = time_entry.stopped?.naive_local().date();
-                        time_entry.stopped = Some(Local.from_local_date(&date).and_time(time).single()?);
-                    }
+                let undo = Undo::SetTimeEntryTimes(client_id, project_id, time_entry_id, time_entry.started, time_entry.stopped);
+                let change = time_entry.change.take()?;
+                if let Err(error) = time_entry.apply_change(&change) {
+                    time_entry.change = Some(change);
+                    time_entry.change_error = Some(error);
+                    return None
                 }
                 let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
                     id: time_entry_id.to_string(),
                     started: time_entry.started.clone(),
                     stopped: time_entry.stopped.clone(),
                 };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
                 orders.perform_cmd(async move { Msg::ChangesSaved(
-                    graphql::send_operation(
-                        graphql::mutations::time_entry::set_times::Mutation::build(&args)
-                    ).await.err()
+                    backend.set_time_entry_times(args).await.err(),
+                    Some(undo),
                 )});
                 Some(())
             };
             save_time_entry_change();
         },
 
+        Msg::ToggleNewTimeEntryForm(client_id, project_id) => {
+            let mut toggle_new_time_entry_form = move || -> Option<()> {
+                let project = model
+                    .clients
+                    .loaded_mut()?
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?;
+
+                project.new_time_entry = if project.new_time_entry.is_some() {
+                    None
+                } else {
+                    Some(NewTimeEntry {
+                        name: project
+                            .time_entries
+                            .values()
+                            .next_back()
+                            .map(|time_entry| time_entry.name.clone())
+                            .unwrap_or_default(),
+                        date: Local::today().format("%F").to_string(),
+                        started: String::new(),
+                        stopped: String::new(),
+                        duration: String::new(),
+                        error: None,
+                    })
+                };
+                Some(())
+            };
+            toggle_new_time_entry_form();
+        },
+        Msg::NewTimeEntryNameChanged(client_id, project_id, name) => {
+            if let Some(new_time_entry) = new_time_entry_mut(model, client_id, project_id) {
+                new_time_entry.name = name;
+            }
+        },
+        Msg::NewTimeEntryDateChanged(client_id, project_id, date) => {
+            if let Some(new_time_entry) = new_time_entry_mut(model, client_id, project_id) {
+                new_time_entry.date = date;
+            }
+        },
+        Msg::NewTimeEntryStartedChanged(client_id, project_id, started) => {
+            if let Some(new_time_entry) = new_time_entry_mut(model, client_id, project_id) {
+                new_time_entry.started = started;
+            }
+        },
+        Msg::NewTimeEntryStoppedChanged(client_id, project_id, stopped) => {
+            if let Some(new_time_entry) = new_time_entry_mut(model, client_id, project_id) {
+                new_time_entry.stopped = stopped;
+            }
+        },
+        Msg::NewTimeEntryDurationChanged(client_id, project_id, duration) => {
+            if let Some(new_time_entry) = new_time_entry_mut(model, client_id, project_id) {
+                new_time_entry.duration = duration;
+            }
+        },
+        Msg::AddTimeEntry(client_id, project_id) => {
+            let mut add_time_entry = move || -> Option<()> {
+                let project = model
+                    .clients
+                    .loaded_mut()?
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?;
+
+                let new_time_entry = project.new_time_entry.as_mut()?;
+                let (started, stopped) = match new_time_entry.validate() {
+                    Ok(times) => times,
+                    Err(error) => {
+                        new_time_entry.error = Some(error);
+                        return None;
+                    }
+                };
+                let time_entry_id = TimeEntryId::new();
+                let time_entry = TimeEntry {
+                    name: new_time_entry.name.clone(),
+                    started,
+                    stopped: Some(stopped),
+                    change: None,
+                    change_error: None,
+                    split_time: None,
+                };
+                project.new_time_entry = None;
+
+                let args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
+                    id: time_entry_id.to_string(),
+                    name: time_entry.name.clone(),
+                    started: time_entry.started,
+                    stopped: time_entry.stopped,
+                    project: project_id.to_string(),
+                };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
+                orders.perform_cmd(async move { Msg::ChangesSaved(
+                    backend.add_time_entry(args).await.err(),
+                    Some(Undo::RemoveTimeEntry(client_id, project_id, time_entry_id)),
+                )});
+
+                project.time_entries.insert(time_entry_id, time_entry);
+                Some(())
+            };
+            add_time_entry();
+        },
+
+        Msg::ToggleSplitTimeEntryForm(client_id, project_id, time_entry_id) => {
+            let mut toggle_split_time_entry_form = move || -> Option<()> {
+                let time_entry = model
+                    .clients
+                    .loaded_mut()?
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .get_mut(&time_entry_id)?;
+
+                time_entry.split_time = if time_entry.split_time.is_some() {
+                    None
+                } else {
+                    let stopped = time_entry.stopped.unwrap_or_else(Local::now);
+                    let middle = time_entry.started + (stopped - time_entry.started) / 2;
+                    Some(middle.format("%R").to_string())
+                };
+                time_entry.change_error = None;
+                Some(())
+            };
+            toggle_split_time_entry_form();
+        },
+        Msg::SplitTimeChanged(client_id, project_id, time_entry_id, split_time) => {
+            let mut set_split_time = move |split_time| -> Option<()> {
+                Some(model
+                    .clients
+                    .loaded_mut()?
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .get_mut(&time_entry_id)?
+                    .split_time = Some(split_time))
+            };
+            set_split_time(split_time);
+        },
+        Msg::SplitTimeEntry(client_id, project_id, time_entry_id) => {
+            let mut split_time_entry = move || -> Option<()> {
+                let time_entries = &mut model
+                    .clients
+                    .loaded_mut()?
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries;
+
+                let time_entry = time_entries.get_mut(&time_entry_id)?;
+                let split = match time_entry.split_at(time_entry.split_time.as_ref()?) {
+                    Ok(split) => split,
+                    Err(error) => {
+                        time_entry.change_error = Some(error);
+                        return None
+                    }
+                };
+                time_entry.split_time = None;
+                time_entry.change_error = None;
+
+                let new_time_entry_id = TimeEntryId::new();
+                let new_time_entry = TimeEntry {
+                    name: time_entry.name.clone(),
+                    started: split,
+                    stopped: time_entry.stopped,
+                    change: None,
+                    change_error: None,
+                    split_time: None,
+                };
+                let undo = Undo::SetTimeEntryTimes(client_id, project_id, time_entry_id, time_entry.started, time_entry.stopped);
+                time_entry.stopped = Some(split);
+
+                let set_times_args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+                    id: time_entry_id.to_string(),
+                    started: time_entry.started,
+                    stopped: time_entry.stopped,
+                };
+                let add_args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
+                    id: new_time_entry_id.to_string(),
+                    name: new_time_entry.name.clone(),
+                    started: new_time_entry.started,
+                    stopped: new_time_entry.stopped,
+                    project: project_id.to_string(),
+                };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
+                orders.perform_cmd(async move { Msg::ChangesSaved(
+                    backend.set_time_entry_times(set_times_args).await.err(),
+                    Some(undo),
+                )});
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
+                orders.perform_cmd(async move { Msg::ChangesSaved(
+                    backend.add_time_entry(add_args).await.err(),
+                    Some(Undo::RemoveTimeEntry(client_id, project_id, new_time_entry_id)),
+                )});
+
+                time_entries.insert(new_time_entry_id, new_time_entry);
+                Some(())
+            };
+            split_time_entry();
+        },
+        Msg::MergeWithPreviousTimeEntry(client_id, project_id, time_entry_id) => {
+            let mut merge_with_previous_time_entry = move || -> Option<()> {
+                let project = model
+                    .clients
+                    .loaded_mut()?
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?;
+
+                let previous_time_entry_id = previous_time_entry_id(project, time_entry_id)?;
+                let time_entry = project.time_entries.get(&time_entry_id)?;
+                let previous_time_entry = project.time_entries.get(&previous_time_entry_id)?;
+
+                let mut message = format!(
+                    "Time Entry \"{}\" will be merged into \"{}\".", 
+                    time_entry.name, 
+                    previous_time_entry.name,
+                );
+                // The merged entry spans both entries, so the time between them is tracked, too.
+                if let Some(previous_stopped) = previous_time_entry.stopped {
+                    let gap = time_entry.started - previous_stopped;
+                    if gap.num_minutes() > 0 {
+                        message.push_str(&format!(
+                            " The gap of {} between them will be included.",
+                            common::format_hours_and_minutes(&gap),
+                        ));
+                    }
+                }
+                if not(matches!(window().confirm_with_message(&message), Ok(true))) {
+                    return None
+                }
+
+                let time_entry = project.time_entries.remove(&time_entry_id)?;
+                let previous_time_entry = project.time_entries.get_mut(&previous_time_entry_id)?;
+                let set_times_undo = Undo::SetTimeEntryTimes(
+                    client_id, 
+                    project_id, 
+                    previous_time_entry_id, 
+                    previous_time_entry.started, 
+                    previous_time_entry.stopped,
+                );
+                previous_time_entry.stopped = match (previous_time_entry.stopped, time_entry.stopped) {
+                    (Some(previous_stopped), Some(stopped)) => Some(previous_stopped.max(stopped)),
+                    _ => None,
+                };
+
+                let set_times_args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+                    id: previous_time_entry_id.to_string(),
+                    started: previous_time_entry.started,
+                    stopped: previous_time_entry.stopped,
+                };
+                let delete_args = graphql::mutations::time_entry::delete::DeleteTimeEntryArguments {
+                    id: time_entry_id.to_string(),
+                };
+                let delete_undo = Undo::RestoreTimeEntry(client_id, project_id, time_entry_id, time_entry);
+
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
+                orders.perform_cmd(async move { Msg::ChangesSaved(
+                    backend.set_time_entry_times(set_times_args).await.err(),
+                    Some(set_times_undo),
+                )});
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
+                orders.perform_cmd(async move { Msg::ChangesSaved(
+                    backend.delete_time_entry(delete_args).await.err(),
+                    Some(delete_undo),
+                )});
+                Some(())
+            };
+            merge_with_previous_time_entry();
+        },
+
+        Msg::MoveTimeEntry(client_id, project_id, time_entry_id, target) => {
+            let mut move_time_entry_to_project = move || -> Option<()> {
+                let mut target = target.split('/');
+                let to_client_id: ClientId = target.next()?.parse().ok()?;
+                let to_project_id: ProjectId = target.next()?.parse().ok()?;
+                if (to_client_id, to_project_id) == (client_id, project_id) {
+                    return None
+                }
+                let clients = model.clients.loaded_mut()?;
+                move_time_entry(clients, client_id, project_id, to_client_id, to_project_id, time_entry_id)?;
+
+                let args = graphql::mutations::time_entry::set_project::SetTimeEntryProjectArguments {
+                    id: time_entry_id.to_string(),
+                    project: to_project_id.to_string(),
+                };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
+                orders.perform_cmd(async move { Msg::ChangesSaved(
+                    backend.set_time_entry_project(args).await.err(),
+                    Some(Undo::MoveTimeEntry(to_client_id, to_project_id, client_id, project_id, time_entry_id)),
+                )});
+                Some(())
+            };
+            move_time_entry_to_project();
+        },
+
+        Msg::TrimOverlappedTimeEntry(time_entry_id) => {
+            let mut trim_overlapped_time_entry = move || -> Option<()> {
+                let clients = model.clients.loaded_mut()?;
+                let overlap = *find_overlaps(clients).get(&time_entry_id)?;
+                let started = clients
+                    .values()
+                    .find_map(|client| client.projects.values().find_map(|project| project.time_entries.get(&time_entry_id)))?
+                    .started;
+
+                let previous_time_entry = clients
+                    .get_mut(&overlap.client_id)?
+                    .projects
+                    .get_mut(&overlap.project_id)?
+                    .time_entries
+                    .get_mut(&overlap.time_entry_id)?;
+                previous_time_entry.stopped?;
+
+                let undo = Undo::SetTimeEntryTimes(
+                    overlap.client_id, 
+                    overlap.project_id, 
+                    overlap.time_entry_id, 
+                    previous_time_entry.started, 
+                    previous_time_entry.stopped,
+                );
+                previous_time_entry.stopped = Some(started);
+
+                let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+                    id: overlap.time_entry_id.to_string(),
+                    started: previous_time_entry.started,
+                    stopped: previous_time_entry.stopped,
+                };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
+                orders.perform_cmd(async move { Msg::ChangesSaved(
+                    backend.set_time_entry_times(args).await.err(),
+                    Some(undo),
+                )});
+                Some(())
+            };
+            trim_overlapped_time_entry();
+        },
+        Msg::ShiftOverlappingTimeEntry(client_id, project_id, time_entry_id) => {
+            let mut shift_overlapping_time_entry = move || -> Option<()> {
+                let clients = model.clients.loaded_mut()?;
+                let overlap = *find_overlaps(clients).get(&time_entry_id)?;
+                let (_, previous_time_entry) = find_time_entry(clients, overlap)?;
+                let previous_stopped = previous_time_entry.stopped?;
+
+                let time_entry = clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .get_mut(&time_entry_id)?;
+
+                let undo = Undo::SetTimeEntryTimes(client_id, project_id, time_entry_id, time_entry.started, time_entry.stopped);
+                let shift = previous_stopped - time_entry.started;
+                time_entry.started = time_entry.started + shift;
+                time_entry.stopped = time_entry.stopped.map(|stopped| stopped + shift);
+
+                let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+                    id: time_entry_id.to_string(),
+                    started: time_entry.started,
+                    stopped: time_entry.stopped,
+                };
+                let backend = ctx.backend()?;
+                model.changes_status.request_started();
+                orders.perform_cmd(async move { Msg::ChangesSaved(
+                    backend.set_time_entry_times(args).await.err(),
+                    Some(undo),
+                )});
+                Some(())
+            };
+            shift_overlapping_time_entry();
+        },
+
+        Msg::ToggleExportForm => {
+            model.export = if model.export.is_some() {
+                None
+            } else {
+                Some(Export::default())
+            };
+        },
+        Msg::ExportFromChanged(from) => {
+            if let Some(export) = &mut model.export {
+                export.from = from;
+            }
+        },
+        Msg::ExportToChanged(to) => {
+            if let Some(export) = &mut model.export {
+                export.to = to;
+            }
+        },
+        Msg::ExportClientSelected(client) => {
+            if let Some(export) = &mut model.export {
+                export.client = client.parse().ok();
+            }
+        },
+        Msg::Export => {
+            let mut export = move || -> Option<()> {
+                let clients = model.clients.loaded()?;
+                let export = model.export.as_mut()?;
+
+                let result = export_csv(clients, export).and_then(|csv| {
+                    csv::download("time_entries.csv", &csv)
+                        .map_err(|error| format!("The download failed: {:?}", error))
+                });
+                export.error = result.err();
+                Some(())
+            };
+            export();
+        },
+
         Msg::OnSecondTick => (),
     }
+
+    if running_time_entries_changed {
+        if let Some(clients) = model.clients.loaded() {
+            save_running_time_entries(clients);
+            orders.notify(RunningTimeEntryChanged(running_time_entry(clients)));
+        }
+    }
+}
+
+fn running_time_entry(clients: &BTreeMap<ClientId, Client>) -> Option<RunningTimeEntry> {
+    clients.iter().find_map(|(client_id, client)| {
+        client.projects.iter().find_map(|(project_id, project)| {
+            project
+                .time_entries
+                .iter()
+                .find(|(_, time_entry)| time_entry.stopped.is_none())
+                .map(|(time_entry_id, time_entry)| RunningTimeEntry {
+                    client_id: *client_id,
+                    project_id: *project_id,
+                    time_entry_id: *time_entry_id,
+                    name: time_entry.name.clone(),
+                    started: time_entry.started,
+                })
+        })
+    })
+}
+
+// ---- Split & Merge ----
+
+impl TimeEntry {
+    // The split time is looked for on the start date first, then on the stop date.
+    fn split_at(&self, split_time: &str) -> parser::Result<DateTime<Local>> {
+        let time = parser::parse_time(split_time)?;
+        let stopped = self.stopped.unwrap_or_else(Local::now);
+        let dates = [self.started.naive_local().date(), stopped.naive_local().date()];
+
+        dates
+            .iter()
+            .filter_map(|date| parser::local_date_time(*date, time).ok())
+            .find(|split| *split > self.started && *split < stopped)
+            .ok_or_else(|| "The split time has to be between the start and the end.".to_owned())
+    }
+}
+
+// The time entry of the same project that started right before the given one.
+fn previous_time_entry_id(project: &Project, time_entry_id: TimeEntryId) -> Option<TimeEntryId> {
+    let started = project.time_entries.get(&time_entry_id)?.started;
+    project
+        .time_entries
+        .iter()
+        .filter(|(id, time_entry)| (time_entry.started, **id) < (started, time_entry_id))
+        .max_by_key(|(id, time_entry)| (time_entry.started, **id))
+        .map(|(id, _)| *id)
+}
+
+// ---- Overlaps ----
+
+// The earlier of two overlapping time entries.
+#[derive(Debug, Clone, Copy)]
+struct Overlap {
+    client_id: ClientId,
+    project_id: ProjectId,
+    time_entry_id: TimeEntryId,
+}
+
+// Maps each time entry to an earlier time entry (from any project) it overlaps with.
+fn find_overlaps(clients: &BTreeMap<ClientId, Client>) -> BTreeMap<TimeEntryId, Overlap> {
+    let now = Local::now();
+    let mut time_entries = clients
+        .iter()
+        .flat_map(|(client_id, client)| {
+            client.projects.iter().flat_map(move |(project_id, project)| {
+                project.time_entries.iter().map(move |(time_entry_id, time_entry)| {
+                    let overlap = Overlap {
+                        client_id: *client_id,
+                        project_id: *project_id,
+                        time_entry_id: *time_entry_id,
+                    };
+                    (time_entry.started, time_entry.stopped.unwrap_or(now), overlap)
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    time_entries.sort_by_key(|(started, _, overlap)| (*started, overlap.time_entry_id));
+
+    let mut overlaps = BTreeMap::new();
+    // The time entry that ends the latest among the already processed ones.
+    let mut latest: Option<(DateTime<Local>, Overlap)> = None;
+    for (started, stopped, overlap) in time_entries {
+        match latest {
+            Some((latest_stopped, latest_overlap)) if latest_stopped > started => {
+                overlaps.insert(overlap.time_entry_id, latest_overlap);
+                if stopped > latest_stopped {
+                    latest = Some((stopped, overlap));
+                }
+            },
+            _ => latest = Some((stopped, overlap)),
+        }
+    }
+    overlaps
+}
+
+fn find_time_entry(clients: &BTreeMap<ClientId, Client>, overlap: Overlap) -> Option<(&Project, &TimeEntry)> {
+    let project = clients.get(&overlap.client_id)?.projects.get(&overlap.project_id)?;
+    Some((project, project.time_entries.get(&overlap.time_entry_id)?))
+}
+
+fn new_time_entry_mut(model: &mut Model, client_id: ClientId, project_id: ProjectId) -> Option<&mut NewTimeEntry> {
+    model
+        .clients
+        .loaded_mut()?
+        .get_mut(&client_id)?
+        .projects
+        .get_mut(&project_id)?
+        .new_time_entry
+        .as_mut()
+}
+
+impl NewTimeEntry {
+    fn validate(&self) -> parser::Result<(DateTime<Local>, DateTime<Local>)> {
+        let date = parser::parse_date(&self.date)?;
+        let started = parser::local_date_time(date, parser::parse_time(&self.started)?)?;
+
+        let stopped = match (self.stopped.trim(), self.duration.trim()) {
+            ("", "") => Err("Fill the end or the duration.")?,
+            ("", duration) => started + parser::parse_duration(duration)?,
+            (stopped, _) => {
+                let stopped = parser::parse_time(stopped)?;
+                // An end before the start means the time entry crosses midnight.
+                let stopped_date = if stopped < started.time() { date.succ() } else { date };
+                parser::local_date_time(stopped_date, stopped)?
+            },
+        };
+        if stopped <= started {
+            Err("The end has to be after the start.")?
+        }
+        Ok((started, stopped))
+    }
+}
+
+impl TimeEntry {
+    fn apply_change(&mut self, change: &TimeEntryChange) -> parser::Result<()> {
+        let mut started = self.started;
+        let mut stopped = self.stopped;
+        match change {
+            TimeEntryChange::StartedDate(date) => {
+                let date = parser::parse_date(date)?;
+                started = parser::local_date_time(date, started.time())?;
+            }
+            TimeEntryChange::StartedTime(time) => {
+                let time = parser::parse_time(time)?;
+                started = parser::local_date_time(started.naive_local().date(), time)?;
+            }
+            TimeEntryChange::Duration(duration) => {
+                let duration = parser::parse_duration(duration)?;
+                stopped = Some(started + duration);
+            }
+            TimeEntryChange::StoppedDate(date) => {
+                let date = parser::parse_date(date)?;
+                let time = stopped.ok_or("The time entry is still running.")?.time();
+                stopped = Some(parser::local_date_time(date, time)?);
+            }
+            TimeEntryChange::StoppedTime(time) => {
+                let time = parser::parse_time(time)?;
+                let date = stopped.ok_or("The time entry is still running.")?.naive_local().date();
+                stopped = Some(parser::local_date_time(date, time)?);
+            }
+        }
+        if stopped.unwrap_or_else(Local::now) < started {
+            Err("The time entry can't end before it starts.")?
+        }
+        self.started = started;
+        self.stopped = stopped;
+        Ok(())
+    }
+}
+
+// The name of the last time entry in the project is used when `name` is `None`.
+fn start_time_entry(
+    model: &mut Model, 
+    ctx: &Context, 
+    orders: &mut impl Orders<Msg>, 
+    client_id: ClientId, 
+    project_id: ProjectId, 
+    name: Option<String>,
+) -> Option<()> {
+    let time_entries = &mut model
+        .clients
+        .loaded_mut()?
+        .get_mut(&client_id)?
+        .projects
+        .get_mut(&project_id)?
+        .time_entries;
+
+    let name = name.or_else(|| {
+        time_entries
+            .iter()
+            .next_back()
+            .map(|(_, time_entry)| time_entry.name.to_owned())
+    });
+
+    let time_entry_id = TimeEntryId::new();
+    let time_entry = TimeEntry {
+        name: name.unwrap_or_default(),
+        started: chrono::Local::now(),
+        stopped: None,
+        change: None,
+        change_error: None,
+        split_time: None,
+    };
+    
+    let args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
+        id: time_entry_id.to_string(),
+        name: time_entry.name.clone(),
+        started: time_entry.started.clone(),
+        stopped: None,
+        project: project_id.to_string(),
+    };
+    let backend = ctx.backend()?;
+    model.changes_status.request_started();
+    orders.perform_cmd(async move { Msg::ChangesSaved(
+        backend.add_time_entry(args).await.err(),
+        Some(Undo::RemoveTimeEntry(client_id, project_id, time_entry_id)),
+    )});
+
+    time_entries.insert(time_entry_id, time_entry);
+    Some(())
+}
+
+fn stop_time_entry(
+    model: &mut Model, 
+    ctx: &Context, 
+    orders: &mut impl Orders<Msg>, 
+    client_id: ClientId, 
+    project_id: ProjectId, 
+    stopped: DateTime<Local>,
+) -> Option<()> {
+    let (time_entry_id, time_entry) = model
+        .clients
+        .loaded_mut()?
+        .get_mut(&client_id)?
+        .projects
+        .get_mut(&project_id)?
+        .time_entries
+        .iter_mut()
+        .find(|(_, time_entry)| time_entry.stopped.is_none())?;
+    
+    let undo = Undo::SetTimeEntryTimes(client_id, project_id, *time_entry_id, time_entry.started, time_entry.stopped);
+    time_entry.stopped = Some(stopped);
+
+    let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+        id: time_entry_id.to_string(),
+        started: time_entry.started.clone(),
+        stopped: time_entry.stopped.clone(),
+    };
+    let backend = ctx.backend()?;
+    model.changes_status.request_started();
+    orders.perform_cmd(async move { Msg::ChangesSaved(
+        backend.set_time_entry_times(args).await.err(),
+        Some(undo),
+    )});
+
+    Some(())
+}
+
+// The running time entry is stopped at the start of the idle time 
+// and a new one with the same name starts now.
+fn discard_idle_time(
+    model: &mut Model, 
+    ctx: &Context, 
+    orders: &mut impl Orders<Msg>, 
+    client_id: ClientId, 
+    project_id: ProjectId, 
+    idle_since: DateTime<Local>,
+) -> Option<()> {
+    let time_entries = &mut model
+        .clients
+        .loaded_mut()?
+        .get_mut(&client_id)?
+        .projects
+        .get_mut(&project_id)?
+        .time_entries;
+
+    let (time_entry_id, time_entry) = time_entries
+        .iter_mut()
+        .find(|(_, time_entry)| time_entry.stopped.is_none())?;
+    let time_entry_id = *time_entry_id;
+    
+    let undo = Undo::SetTimeEntryTimes(client_id, project_id, time_entry_id, time_entry.started, time_entry.stopped);
+    time_entry.stopped = Some(idle_since.max(time_entry.started));
+
+    let new_time_entry_id = TimeEntryId::new();
+    let new_time_entry = TimeEntry {
+        name: time_entry.name.clone(),
+        started: chrono::Local::now(),
+        stopped: None,
+        change: None,
+        change_error: None,
+        split_time: None,
+    };
+
+    let set_times_args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+        id: time_entry_id.to_string(),
+        started: time_entry.started,
+        stopped: time_entry.stopped,
+    };
+    let add_args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
+        id: new_time_entry_id.to_string(),
+        name: new_time_entry.name.clone(),
+        started: new_time_entry.started,
+        stopped: None,
+        project: project_id.to_string(),
+    };
+    let backend = ctx.backend()?;
+    model.changes_status.request_started();
+    orders.perform_cmd(async move { Msg::ChangesSaved(
+        backend.set_time_entry_times(set_times_args).await.err(),
+        Some(undo),
+    )});
+    let backend = ctx.backend()?;
+    model.changes_status.request_started();
+    orders.perform_cmd(async move { Msg::ChangesSaved(
+        backend.add_time_entry(add_args).await.err(),
+        Some(Undo::RemoveTimeEntry(client_id, project_id, new_time_entry_id)),
+    )});
+
+    time_entries.insert(new_time_entry_id, new_time_entry);
+    Some(())
+}
+
+// Only one time entry may run at a time.
+fn stop_running_time_entries(model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) {
+    let clients = match model.clients.loaded_mut() {
+        Some(clients) => clients,
+        None => return,
+    };
+    let now = chrono::Local::now();
+
+    for (client_id, client) in clients {
+        for (project_id, project) in &mut client.projects {
+            for (time_entry_id, time_entry) in &mut project.time_entries {
+                if time_entry.stopped.is_some() {
+                    continue;
+                }
+                let undo = Undo::SetTimeEntryTimes(*client_id, *project_id, *time_entry_id, time_entry.started, time_entry.stopped);
+                time_entry.stopped = Some(now);
+
+                let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+                    id: time_entry_id.to_string(),
+                    started: time_entry.started,
+                    stopped: time_entry.stopped,
+                };
+                if let Some(backend) = ctx.backend() {
+                    model.changes_status.request_started();
+                    orders.perform_cmd(async move { Msg::ChangesSaved(
+                        backend.set_time_entry_times(args).await.err(),
+                        Some(undo),
+                    )});
+                }
+            }
+        }
+    }
+}
+
+// ---- Export ----
+
+// Rows ordered by `started`. Running time entries are skipped - they have no `stopped` time yet.
+fn export_csv(clients: &BTreeMap<ClientId, Client>, export: &Export) -> parser::Result<String> {
+    let optional_date = |text: &str| -> parser::Result<Option<NaiveDate>> {
+        if text.trim().is_empty() {
+            Ok(None)
+        } else {
+            parser::parse_date(text).map(Some)
+        }
+    };
+    let from = optional_date(&export.from)?;
+    let to = optional_date(&export.to)?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if to < from {
+            Err("The end date is before the start date.".to_owned())?
+        }
+    }
+
+    let mut time_entries = Vec::new();
+    for (client_id, client) in clients {
+        if export.client.map(|id| id != *client_id).unwrap_or_default() {
+            continue
+        }
+        for project in client.projects.values() {
+            for time_entry in project.time_entries.values() {
+                // Running time entries have no end yet, so they couldn't be imported back.
+                let stopped = match time_entry.stopped {
+                    Some(stopped) => stopped,
+                    None => continue,
+                };
+                let date = time_entry.started.naive_local().date();
+                if from.map(|from| date < from).unwrap_or_default() || to.map(|to| date > to).unwrap_or_default() {
+                    continue
+                }
+                time_entries.push((client, project, time_entry, stopped));
+            }
+        }
+    }
+    time_entries.sort_by_key(|(_, _, time_entry, _)| time_entry.started);
+
+    let date_time_format = "%Y-%m-%d %H:%M:%S";
+    let header = ["client", "project", "entry name", "started", "stopped", "duration (hours)", "duration (H:MM)"];
+    let rows = time_entries.into_iter().map(|(client, project, time_entry, stopped)| {
+        let duration = stopped - time_entry.started;
+        vec![
+            client.name.clone(),
+            project.name.clone(),
+            time_entry.name.clone(),
+            time_entry.started.format(date_time_format).to_string(),
+            stopped.format(date_time_format).to_string(),
+            format!("{:.2}", duration.num_seconds() as f64 / 3600.),
+            common::format_hours_and_minutes(&duration),
+        ]
+    });
+    Ok(csv::write(
+        std::iter::once(header.iter().map(|column| column.to_string()).collect()).chain(rows)
+    ))
 }
 
 // ------ ------
 //     View
 // ------ ------
 
-pub fn view(model: &Model) -> Node<Msg> {
+pub fn view(model: &Model, ctx: &Context) -> Node<Msg> {
     section![
         h1![C!["title", "ml-6", "mt-6", "mb-5"],
             "Time Tracker",
+            common::view_changes_status(&model.changes_status),
         ],
         div![C!["columns", "is-centered"],
             div![C!["column", "is-two-thirds"],
+                common::view_errors(&model.errors, || Msg::ClearErrors),
+                model.clients.loaded().map(view_running_time_entry),
+                model.clients.loaded().map(|clients| view_export(model.export.as_ref(), clients)),
                 match &model.clients {
-                    RemoteData::NotAsked | RemoteData::Loading => {
+                    RemoteData::NotAsked => {
+                        page::log_in_required::view().into_nodes()
+                    },
+                    RemoteData::Loading => {
                         progress![C!["progress", "is-link", "mt-6"]].into_nodes()
                     },
+                    RemoteData::Failed => Vec::new(),
                     RemoteData::Loaded(clients) => {
-                        clients.iter().rev().map(|(client_id, client)| view_client(*client_id, client)).collect()
+                        let overlaps = find_overlaps(clients);
+                        clients
+                            .iter()
+                            .rev()
+                            .map(|(client_id, client)| view_client(*client_id, client, clients, &overlaps))
+                            .collect()
                     }
                 }
             ]
@@ -505,18 +1537,141 @@ pub fn view(model: &Model) -> Node<Msg> {
     ]
 }
 
-fn view_client(client_id: ClientId, client: &Client) -> Node<Msg> {
+fn view_export(export: Option<&Export>, clients: &BTreeMap<ClientId, Client>) -> Node<Msg> {
+    let export = match export {
+        Some(export) => export,
+        None => {
+            return div![C!["buttons", "is-right", "mt-4"],
+                button![C!["button", "is-small"],
+                    ev(Ev::Click, |_| Msg::ToggleExportForm),
+                    "Export CSV",
+                ],
+            ]
+        },
+    };
+    let date_field = |label: &str, value: &str, on_input: fn(String) -> Msg| {
+        div![C!["field"],
+            label![C!["label"], label],
+            div![C!["control"],
+                input![C!["input"],
+                    attrs!{At::Placeholder => "2020-01-15, yesterday, mon", At::Value => value},
+                    input_ev(Ev::Input, on_input),
+                ],
+            ],
+        ]
+    };
+    div![C!["box", "mt-4"],
+        div![C!["columns"],
+            div![C!["column"], date_field("From", &export.from, Msg::ExportFromChanged)],
+            div![C!["column"], date_field("To", &export.to, Msg::ExportToChanged)],
+            div![C!["column"],
+                div![C!["field"],
+                    label![C!["label"], "Client"],
+                    div![C!["control"],
+                        div![C!["select"],
+                            select![
+                                option![attrs!{At::Value => ""}, "All clients"],
+                                clients.iter().map(|(client_id, client)| {
+                                    option![
+                                        attrs!{
+                                            At::Value => client_id,
+                                            At::Selected => (export.client == Some(*client_id)).as_at_value(),
+                                        },
+                                        &client.name,
+                                    ]
+                                }),
+                                input_ev(Ev::Change, Msg::ExportClientSelected),
+                            ],
+                        ],
+                    ],
+                ],
+            ],
+        ],
+        p![C!["help", "mb-3"], "Running time entries are not exported."],
+        export.error.as_ref().map(|error| {
+            p![C!["help", "is-danger", "mb-3"], error]
+        }),
+        div![C!["buttons"],
+            button![C!["button", "is-primary"],
+                ev(Ev::Click, |_| Msg::Export),
+                "Download",
+            ],
+            button![C!["button"],
+                ev(Ev::Click, |_| Msg::ToggleExportForm),
+                "Cancel",
+            ],
+        ],
+    ]
+}
+
+fn view_running_time_entry(clients: &BTreeMap<ClientId, Client>) -> Node<Msg> {
+    let running_time_entry = clients.iter().find_map(|(client_id, client)| {
+        client.projects.iter().find_map(|(project_id, project)| {
+            project
+                .time_entries
+                .values()
+                .find(|time_entry| time_entry.stopped.is_none())
+                .map(|time_entry| (*client_id, client, *project_id, project, time_entry))
+        })
+    });
+    let (client_id, client, project_id, project, time_entry) = match running_time_entry {
+        Some(running_time_entry) => running_time_entry,
+        None => return empty![],
+    };
+
+    div![C!["notification", "is-warning", "mt-6"],
+        div![C!["level", "is-mobile"],
+            div![C!["level-left"],
+                div![C!["level-item"],
+                    div![
+                        p![C!["heading"], "Currently tracking"],
+                        p![C!["is-size-5"], 
+                            strong![&client.name], " / ", &project.name,
+                            IF!(not(time_entry.name.is_empty()) => format!(" – {}", time_entry.name)),
+                        ],
+                    ],
+                ],
+            ],
+            div![C!["level-right"],
+                div![C!["level-item", "is-size-4", "has-text-weight-bold"],
+                    common::format_duration(&(chrono::Local::now() - time_entry.started)),
+                ],
+                div![C!["level-item"],
+                    button![C!["button", "is-link", "is-rounded"],
+                        ev(Ev::Click, move |_| Msg::Stop(client_id, project_id)),
+                        "Stop",
+                    ],
+                ],
+            ],
+        ],
+    ]
+}
+
+fn view_client(
+    client_id: ClientId, 
+    client: &Client, 
+    clients: &BTreeMap<ClientId, Client>, 
+    overlaps: &BTreeMap<TimeEntryId, Overlap>,
+) -> Node<Msg> {
     div![C!["box", "has-background-link", "mt-6",],
         div![C!["level", "is-mobile"],
             div![C!["is-size-3", "has-text-link-light"], 
                 &client.name,
             ],
         ],
-        client.projects.iter().rev().map(|(project_id, project)| view_project(client_id, *project_id, project)),
+        client.projects.iter().rev().map(|(project_id, project)| {
+            view_project(client_id, *project_id, project, clients, overlaps)
+        }),
     ]
 }
 
-fn view_project(client_id: ClientId, project_id: ProjectId, project: &Project) -> Node<Msg> {
+fn view_project(
+    client_id: ClientId, 
+    project_id: ProjectId, 
+    project: &Project, 
+    clients: &BTreeMap<ClientId, Client>, 
+    overlaps: &BTreeMap<TimeEntryId, Overlap>,
+) -> Node<Msg> {
     let active_time_entry = project
         .time_entries
         .iter()
@@ -527,14 +1682,63 @@ fn view_project(client_id: ClientId, project_id: ProjectId, project: &Project) -
             div![C!["is-size-4"], 
                 &project.name,
             ],
-            view_start_stop_button(client_id, project_id, active_time_entry.is_some()),
+            div![C!["buttons"],
+                button![C!["button", "is-rounded", IF!(project.new_time_entry.is_some() => "is-active")],
+                    ev(Ev::Click, move |_| Msg::ToggleNewTimeEntryForm(client_id, project_id)),
+                    span!["Add entry"],
+                ],
+                view_start_stop_button(client_id, project_id, active_time_entry.is_some()),
+            ],
         ],
+        project.new_time_entry.as_ref().map(|new_time_entry| {
+            view_new_time_entry(client_id, project_id, new_time_entry)
+        }),
         project.time_entries.iter().rev().map(|(time_entry_id, time_entry)| {
-            view_time_entry(client_id, project_id, *time_entry_id, time_entry)
+            let overlap = overlaps
+                .get(time_entry_id)
+                .and_then(|overlap| find_time_entry(clients, *overlap));
+            let mergeable = previous_time_entry_id(project, *time_entry_id).is_some();
+            view_time_entry(client_id, project_id, *time_entry_id, time_entry, overlap, mergeable, clients)
         }),
     ]
 }
 
+fn view_new_time_entry(client_id: ClientId, project_id: ProjectId, new_time_entry: &NewTimeEntry) -> Node<Msg> {
+    let field = |label: &str, placeholder: &str, value: &str, on_input: fn(ClientId, ProjectId, String) -> Msg| {
+        div![C!["field"],
+            label![C!["label"], label],
+            div![C!["control"],
+                input![C!["input"],
+                    attrs!{At::Placeholder => placeholder, At::Value => value},
+                    input_ev(Ev::Input, move |value| on_input(client_id, project_id, value)),
+                ],
+            ],
+        ]
+    };
+    div![C!["box"],
+        field("Name", "", &new_time_entry.name, Msg::NewTimeEntryNameChanged),
+        div![C!["columns"],
+            div![C!["column"], field("Date", "2020-01-15, yesterday, mon", &new_time_entry.date, Msg::NewTimeEntryDateChanged)],
+            div![C!["column"], field("Start", "9am, 14:05", &new_time_entry.started, Msg::NewTimeEntryStartedChanged)],
+            div![C!["column"], field("End", "5pm, 17:30", &new_time_entry.stopped, Msg::NewTimeEntryStoppedChanged)],
+            div![C!["column"], field("or Duration", "1h30m, 1.5h, 1:30", &new_time_entry.duration, Msg::NewTimeEntryDurationChanged)],
+        ],
+        new_time_entry.error.as_ref().map(|error| {
+            p![C!["help", "is-danger", "mb-3"], error]
+        }),
+        div![C!["buttons"],
+            button![C!["button", "is-primary"],
+                ev(Ev::Click, move |_| Msg::AddTimeEntry(client_id, project_id)),
+                "Add",
+            ],
+            button![C!["button"],
+                ev(Ev::Click, move |_| Msg::ToggleNewTimeEntryForm(client_id, project_id)),
+                "Cancel",
+            ],
+        ],
+    ]
+}
+
 fn view_start_stop_button(client_id: ClientId, project_id: ProjectId, started: bool) -> Node<Msg> {
     div![C!["level", "is-mobile"],
         button![C!["button", if started { "is-warning" } else { "is-primary" }, "is-rounded"],
@@ -552,7 +1756,10 @@ fn view_time_entry(
     client_id: ClientId, 
     project_id: ProjectId, 
     time_entry_id: TimeEntryId, 
-    time_entry: &TimeEntry
+    time_entry: &TimeEntry,
+    overlap: Option<(&Project, &TimeEntry)>,
+    mergeable: bool,
+    clients: &BTreeMap<ClientId, Client>,
 ) -> Node<Msg> {
     let active = time_entry.stopped.is_none();
     let stopped = time_entry.stopped.as_ref().cloned().unwrap_or_else(chrono::Local::now);
@@ -567,13 +1774,16 @@ fn view_time_entry(
                     St::Height => rem(3),
                     St::Border => "none",
                     St::BorderBottom => format!("{} {} {}", "solid", if active { LINK_COLOR } else { PRIMARY_COLOR }, px(2)),
-                    St::MaxWidth => percent(85),
+                    St::MaxWidth => percent(if active { 85 } else { 75 }),
                 },
                 attrs!{At::Value => time_entry.name},
                 input_ev(Ev::Input, move |name| Msg::TimeEntryNameChanged(client_id, project_id, time_entry_id, name)),
                 ev(Ev::Change, move |_| Msg::SaveTimeEntryName(client_id, project_id, time_entry_id)),
             ],
-            view_delete_button(move || Msg::DeleteTimeEntry(client_id, project_id, time_entry_id), active),
+            div![C!["buttons", "is-flex-wrap-nowrap"],
+                IF!(not(active) => view_continue_button(client_id, project_id, time_entry_id)),
+                view_delete_button(move || Msg::DeleteTimeEntry(client_id, project_id, time_entry_id), active),
+            ],
         ],
         div![C!["level", "is-mobile", "is-hidden-tablet"], style!{St::MarginBottom => 0},
             view_duration(client_id, project_id, time_entry_id, &duration, time_entry.change.as_ref(), active)
@@ -585,6 +1795,115 @@ fn view_time_entry(
             ],
             view_stopped(client_id, project_id, time_entry_id,  time_entry.change.as_ref(), active, &stopped),
         ],
+        div![C!["level", "is-mobile", "mt-2"],
+            view_project_picker(client_id, project_id, time_entry_id, clients),
+            view_split_and_merge(client_id, project_id, time_entry_id, time_entry.split_time.as_ref(), mergeable),
+        ],
+        time_entry.change_error.as_ref().map(|error| {
+            p![C!["help", "is-size-6", if active { "has-text-danger" } else { "has-text-warning" }], error]
+        }),
+        overlap.map(|(previous_project, previous_time_entry)| {
+            view_overlap(client_id, project_id, time_entry_id, previous_project, previous_time_entry)
+        }),
+    ]
+}
+
+fn view_project_picker(
+    client_id: ClientId, 
+    project_id: ProjectId, 
+    time_entry_id: TimeEntryId, 
+    clients: &BTreeMap<ClientId, Client>,
+) -> Node<Msg> {
+    div![C!["select", "is-small"],
+        select![
+            attrs!{At::Title => "Move to project"},
+            clients.iter().map(|(option_client_id, client)| {
+                optgroup![
+                    attrs!{At::Label => client.name},
+                    client.projects.iter().map(|(option_project_id, project)| {
+                        option![
+                            attrs!{
+                                At::Value => format!("{}/{}", option_client_id, option_project_id),
+                                At::Selected => ((*option_client_id, *option_project_id) == (client_id, project_id)).as_at_value(),
+                            },
+                            &project.name,
+                        ]
+                    }),
+                ]
+            }),
+            input_ev(Ev::Change, move |target| Msg::MoveTimeEntry(client_id, project_id, time_entry_id, target)),
+        ],
+    ]
+}
+
+fn view_split_and_merge(
+    client_id: ClientId, 
+    project_id: ProjectId, 
+    time_entry_id: TimeEntryId, 
+    split_time: Option<&String>,
+    mergeable: bool,
+) -> Node<Msg> {
+    div![C!["buttons", "is-right"],
+        if let Some(split_time) = split_time {
+            nodes![
+                input![C!["input", "is-small"],
+                    style!{St::MaxWidth => rem(6), St::MarginRight => rem(0.5)},
+                    attrs!{At::Value => split_time, At::Placeholder => "14:05"},
+                    input_ev(Ev::Input, move |split_time| Msg::SplitTimeChanged(client_id, project_id, time_entry_id, split_time)),
+                ],
+                button![C!["button", "is-small", "is-primary", "is-inverted"],
+                    ev(Ev::Click, move |_| Msg::SplitTimeEntry(client_id, project_id, time_entry_id)),
+                    "Split here",
+                ],
+                button![C!["button", "is-small", "is-light"],
+                    ev(Ev::Click, move |_| Msg::ToggleSplitTimeEntryForm(client_id, project_id, time_entry_id)),
+                    "Cancel",
+                ],
+            ]
+        } else {
+            nodes![
+                button![C!["button", "is-small", "is-light", "is-outlined"],
+                    ev(Ev::Click, move |_| Msg::ToggleSplitTimeEntryForm(client_id, project_id, time_entry_id)),
+                    "Split",
+                ],
+                IF!(mergeable => button![C!["button", "is-small", "is-light", "is-outlined"],
+                    ev(Ev::Click, move |_| Msg::MergeWithPreviousTimeEntry(client_id, project_id, time_entry_id)),
+                    "Merge with previous",
+                ]),
+            ]
+        },
+    ]
+}
+
+fn view_overlap(
+    client_id: ClientId, 
+    project_id: ProjectId, 
+    time_entry_id: TimeEntryId, 
+    previous_project: &Project,
+    previous_time_entry: &TimeEntry,
+) -> Node<Msg> {
+    let previous_stopped = previous_time_entry.stopped;
+    div![C!["notification", "is-warning", "is-light", "mt-3", "mb-0", "py-3"],
+        div![
+            "Overlaps with ",
+            strong![&previous_time_entry.name],
+            format!(
+                " ({}, {} – {}).", 
+                previous_project.name,
+                previous_time_entry.started.format("%F %X"),
+                previous_stopped.map(|stopped| stopped.format("%X").to_string()).unwrap_or_else(|| "now".to_owned()),
+            ),
+        ],
+        IF!(previous_stopped.is_some() => div![C!["buttons", "mt-2"],
+            button![C!["button", "is-small", "is-warning"],
+                ev(Ev::Click, move |_| Msg::TrimOverlappedTimeEntry(time_entry_id)),
+                "Trim previous entry",
+            ],
+            button![C!["button", "is-small", "is-warning"],
+                ev(Ev::Click, move |_| Msg::ShiftOverlappingTimeEntry(client_id, project_id, time_entry_id)),
+                "Shift this entry",
+            ],
+        ]),
     ]
 }
 
@@ -702,14 +2021,6 @@ fn view_duration(
     time_entry_change: Option<&TimeEntryChange>, 
     for_active_time_entry: bool
 ) -> Node<Msg> {
-    let num_seconds = duration.num_seconds();
-
-    let negative = num_seconds < 0;
-    let num_seconds = num_seconds.abs();
-    let hours = num_seconds / 3600;
-    let minutes = num_seconds % 3600 / 60;
-    let seconds = num_seconds % 60;
-
     input![C!["input", "has-text-centered", "is-size-4", if for_active_time_entry { "has-text-dark" } else { "has-text-link-light" }], 
         style!{
             St::Margin => "auto",
@@ -727,7 +2038,7 @@ fn view_duration(
             At::Value => if let Some(TimeEntryChange::Duration(duration)) = time_entry_change {
                 duration.to_owned()
             } else {
-                format!("{}{}:{:02}:{:02}", if negative { "-" } else { "" }, hours, minutes, seconds)
+                common::format_duration(duration)
             }
         },
         input_ev(Ev::Input, move |duration| Msg::TimeEntryDurationChanged(client_id, project_id, time_entry_id, duration)),
@@ -735,6 +2046,19 @@ fn view_duration(
     ]
 }
 
+fn view_continue_button(client_id: ClientId, project_id: ProjectId, time_entry_id: TimeEntryId) -> Node<Msg> {
+    button![C!["button", "is-primary", "is-rounded"],
+        style!{
+            St::Width => 0,
+        },
+        attrs!{At::Title => "Continue"},
+        ev(Ev::Click, move |_| Msg::Continue(client_id, project_id, time_entry_id)),
+        span![C!["icon"],
+            i![C!["fas", "fa-play"]]
+        ],
+    ]
+}
+
 fn view_delete_button(on_click: impl Fn() -> Msg + Clone + 'static, for_active_time_entry: bool) -> Node<Msg> {
     button![C!["button", if for_active_time_entry { "is-link" } else { "is-primary" }, "is-rounded"],
         style!{
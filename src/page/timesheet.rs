@@ -0,0 +1,583 @@
+use seed::{prelude::*, *};
+
+use chrono::{prelude::*, Duration};
+use ulid::Ulid;
+
+use std::collections::BTreeMap;
+use std::ops::Add;
+
+use crate::{backend::{Backend, BackendFuture}, graphql, page, parser, Context};
+use crate::page::common::{self, ChangesStatus, RemoteData};
+
+type ClientId = Ulid;
+type ProjectId = Ulid;
+type TimeEntryId = Ulid;
+
+type CellKey = (ClientId, ProjectId, NaiveDate);
+
+// New time entries created from the grid start at this hour.
+const NEW_TIME_ENTRY_HOUR: u32 = 9;
+
+// ------ ------
+//     Init
+// ------ ------
+
+pub fn init(_: Url, orders: &mut impl Orders<Msg>, ctx: &Context) -> Model {
+    let clients = match (ctx.user_id(), ctx.backend()) {
+        _ if not(ctx.auth_initialized) => RemoteData::Loading,
+        (Some(user_id), Some(backend)) => {
+            orders.perform_cmd(async move { Msg::ClientsFetched(
+                request_clients(backend, user_id).await
+            )});
+            RemoteData::Loading
+        },
+        _ => RemoteData::NotAsked,
+    };
+
+    Model {
+        changes_status: ChangesStatus::NoChanges,
+        errors: Vec::new(),
+        before_unload_handle: orders.stream_with_handle(streams::window_event(Ev::BeforeUnload, Msg::OnBeforeUnload)),
+
+        week_start: week_start(Local::today().naive_local()),
+        cell_changes: BTreeMap::new(),
+        cell_errors: BTreeMap::new(),
+        clients,
+    }
+}
+
+async fn request_clients(
+    backend: Box<dyn Backend>,
+    user_id: String,
+) -> graphql::Result<BTreeMap<ClientId, Client>> {
+    use graphql::queries::clients_with_projects_with_time_entries as query_mod;
+
+    let time_entry_mapper = |time_entry: query_mod::TimeEntry| (
+        time_entry.id.parse().expect("parse time_entry Ulid"),
+        TimeEntry {
+            name: time_entry.name,
+            started: time_entry.started.0.parse().expect("parse time_entry started time"),
+            stopped: time_entry.stopped.map(|time| time.0.parse().expect("parse time_entry stopped time")),
+        }
+    );
+
+    let project_mapper = |project: query_mod::Project| (
+        project.id.parse().expect("parse project Ulid"),
+        Project {
+            name: project.name,
+            time_entries: project.time_entries.into_iter().map(time_entry_mapper).collect(),
+        },
+    );
+
+    let client_mapper = |client: query_mod::Client| (
+        client.id.parse().expect("parse client Ulid"),
+        Client {
+            name: client.name,
+            projects: client.projects.into_iter().map(project_mapper).collect(),
+        }
+    );
+
+    let args = query_mod::ClientsArguments { user: user_id };
+    Ok(
+        backend
+            .clients_with_projects_with_time_entries(args)
+            .await?
+            .into_iter()
+            .map(client_mapper)
+            .collect()
+    )
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+// ------ ------
+//     Model
+// ------ ------
+
+pub struct Model {
+    changes_status: ChangesStatus,
+    errors: Vec<graphql::GraphQLError>,
+    before_unload_handle: StreamHandle,
+
+    // Monday of the displayed week.
+    week_start: NaiveDate,
+    cell_changes: BTreeMap<CellKey, String>,
+    cell_errors: BTreeMap<CellKey, String>,
+    clients: RemoteData<BTreeMap<ClientId, Client>>,
+}
+
+impl Model {
+    fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        let week_start = self.week_start;
+        (0..7).map(move |day| week_start + Duration::days(day))
+    }
+}
+
+// --- Entities ----
+
+#[derive(Debug)]
+pub struct Client {
+    name: String,
+    projects: BTreeMap<ProjectId, Project>,
+}
+
+#[derive(Debug)]
+pub struct Project {
+    name: String,
+    time_entries: BTreeMap<TimeEntryId, TimeEntry>,
+}
+
+impl Project {
+    // Time entries are counted on the day they started.
+    fn day_time_entries(&self, date: NaiveDate) -> impl Iterator<Item = (&TimeEntryId, &TimeEntry)> {
+        self
+            .time_entries
+            .iter()
+            .filter(move |(_, time_entry)| time_entry.started.naive_local().date() == date)
+    }
+
+    fn day_total(&self, date: NaiveDate) -> Duration {
+        self
+            .day_time_entries(date)
+            .map(|(_, time_entry)| time_entry.duration())
+            .fold(Duration::zero(), Duration::add)
+    }
+}
+
+#[derive(Debug)]
+pub struct TimeEntry {
+    name: String,
+    started: DateTime<Local>,
+    stopped: Option<DateTime<Local>>,
+}
+
+impl TimeEntry {
+    fn duration(&self) -> Duration {
+        self.stopped.unwrap_or_else(Local::now) - self.started
+    }
+}
+
+// ---- Undo ----
+
+pub enum Undo {
+    RemoveTimeEntry(ClientId, ProjectId, TimeEntryId),
+    RestoreTimeEntry(ClientId, ProjectId, TimeEntryId, TimeEntry),
+    SetTimeEntryStopped(ClientId, ProjectId, TimeEntryId, Option<DateTime<Local>>),
+}
+
+impl common::Undo<BTreeMap<ClientId, Client>> for Undo {
+    fn apply(self, clients: &mut BTreeMap<ClientId, Client>) -> Option<()> {
+        match self {
+            Self::RemoveTimeEntry(client_id, project_id, time_entry_id) => {
+                clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .remove(&time_entry_id);
+            },
+            Self::RestoreTimeEntry(client_id, project_id, time_entry_id, time_entry) => {
+                clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .insert(time_entry_id, time_entry);
+            },
+            Self::SetTimeEntryStopped(client_id, project_id, time_entry_id, stopped) => {
+                clients
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?
+                    .time_entries
+                    .get_mut(&time_entry_id)?
+                    .stopped = stopped;
+            },
+        }
+        Some(())
+    }
+}
+
+// ------ ------
+//    Update
+// ------ ------
+
+pub enum Msg {
+    ClientsFetched(graphql::Result<BTreeMap<ClientId, Client>>),
+    ChangesSaved(Option<graphql::GraphQLError>, Option<Undo>),
+    OnBeforeUnload(web_sys::Event),
+    ClearErrors,
+
+    PreviousWeek,
+    NextWeek,
+    CurrentWeek,
+
+    CellChanged(ClientId, ProjectId, NaiveDate, String),
+    SaveCell(ClientId, ProjectId, NaiveDate),
+    // The saved input and the result of each mutation.
+    CellSaved(CellKey, String, Vec<(Option<graphql::GraphQLError>, Undo)>),
+}
+
+pub fn update(msg: Msg, model: &mut Model, ctx: &Context, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::ClientsFetched(Ok(clients)) => {
+            model.clients = RemoteData::Loaded(clients);
+        },
+        Msg::ClientsFetched(Err(graphql_error)) => {
+            model.clients = RemoteData::Failed;
+            model.errors.push(graphql_error);
+        },
+
+        Msg::ChangesSaved(error, undo) => {
+            common::changes_saved(error, undo, &mut model.clients, &mut model.changes_status, &mut model.errors, orders);
+        },
+        Msg::OnBeforeUnload(event) => {
+            model.changes_status.on_before_unload(event);
+            orders.skip();
+        },
+
+        Msg::ClearErrors => {
+            model.errors.clear();
+        },
+
+        // ------ Week ------
+
+        Msg::PreviousWeek => {
+            model.week_start = model.week_start - Duration::weeks(1);
+        },
+        Msg::NextWeek => {
+            model.week_start = model.week_start + Duration::weeks(1);
+        },
+        Msg::CurrentWeek => {
+            model.week_start = week_start(Local::today().naive_local());
+        },
+
+        // ------ Cell ------
+
+        Msg::CellChanged(client_id, project_id, date, duration) => {
+            model.cell_changes.insert((client_id, project_id, date), duration);
+        },
+        Msg::SaveCell(client_id, project_id, date) => {
+            let mut save_cell = move || -> Option<()> {
+                let cell_key = (client_id, project_id, date);
+                let input = model.cell_changes.get(&cell_key)?.clone();
+                let project = model
+                    .clients
+                    .loaded_mut()?
+                    .get_mut(&client_id)?
+                    .projects
+                    .get_mut(&project_id)?;
+
+                let validated = parse_cell_duration(&input).and_then(|duration| {
+                    Ok((duration, stopped_day_time_entries(project, date)?))
+                });
+                let (duration, day_time_entries) = match validated {
+                    Ok(validated) => validated,
+                    Err(error) => {
+                        model.cell_errors.insert(cell_key, error);
+                        return None
+                    }
+                };
+                model.cell_errors.remove(&cell_key);
+
+                let mut difference = duration - project.day_total(date);
+
+                let deleted_count = deleted_time_entry_count(&day_time_entries, difference);
+                if deleted_count > 0 {
+                    let message = match deleted_count {
+                        1 => "1 time entry will be deleted.".to_owned(),
+                        count => format!("{} time entries will be deleted.", count),
+                    };
+                    if not(matches!(window().confirm_with_message(&message), Ok(true))) {
+                        model.cell_changes.remove(&cell_key);
+                        return None
+                    }
+                }
+
+                let backend = ctx.backend()?;
+                let mut changes = Vec::<(BackendFuture<()>, Undo)>::new();
+
+                // Add the missing time to the last time entry of the day or create a new one.
+                if difference > Duration::zero() {
+                    if let Some((_, time_entry_id, stopped)) = day_time_entries.last() {
+                        let time_entry = project.time_entries.get_mut(time_entry_id)?;
+                        let undo = Undo::SetTimeEntryStopped(client_id, project_id, *time_entry_id, time_entry.stopped);
+                        time_entry.stopped = Some(*stopped + difference);
+
+                        let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+                            id: time_entry_id.to_string(),
+                            started: time_entry.started,
+                            stopped: time_entry.stopped,
+                        };
+                        changes.push((backend.set_time_entry_times(args), undo));
+                    } else {
+                        let started = parser::local_date_time(date, NaiveTime::from_hms(NEW_TIME_ENTRY_HOUR, 0, 0)).ok()?;
+                        let name = project
+                            .time_entries
+                            .values()
+                            .max_by_key(|time_entry| time_entry.started)
+                            .map(|time_entry| time_entry.name.clone())
+                            .unwrap_or_default();
+
+                        let time_entry_id = TimeEntryId::new();
+                        let time_entry = TimeEntry {
+                            name,
+                            started,
+                            stopped: Some(started + difference),
+                        };
+
+                        let args = graphql::mutations::time_entry::add::AddTimeEntryArguments {
+                            id: time_entry_id.to_string(),
+                            name: time_entry.name.clone(),
+                            started: time_entry.started,
+                            stopped: time_entry.stopped,
+                            project: project_id.to_string(),
+                        };
+                        changes.push((backend.add_time_entry(args), Undo::RemoveTimeEntry(client_id, project_id, time_entry_id)));
+                        project.time_entries.insert(time_entry_id, time_entry);
+                    }
+                }
+
+                // Remove the excess time from the last time entries of the day.
+                for (started, time_entry_id, stopped) in day_time_entries.iter().rev() {
+                    if difference >= Duration::zero() {
+                        break
+                    }
+                    let time_entry_duration = *stopped - *started;
+
+                    if time_entry_duration + difference > Duration::zero() {
+                        let time_entry = project.time_entries.get_mut(time_entry_id)?;
+                        let undo = Undo::SetTimeEntryStopped(client_id, project_id, *time_entry_id, time_entry.stopped);
+                        time_entry.stopped = Some(*stopped + difference);
+                        difference = Duration::zero();
+
+                        let args = graphql::mutations::time_entry::set_times::SetTimeEntryTimesArguments {
+                            id: time_entry_id.to_string(),
+                            started: time_entry.started,
+                            stopped: time_entry.stopped,
+                        };
+                        changes.push((backend.set_time_entry_times(args), undo));
+                    } else {
+                        let time_entry = project.time_entries.remove(time_entry_id)?;
+                        difference = difference + time_entry_duration;
+
+                        let args = graphql::mutations::time_entry::delete::DeleteTimeEntryArguments {
+                            id: time_entry_id.to_string(),
+                        };
+                        let undo = Undo::RestoreTimeEntry(client_id, project_id, *time_entry_id, time_entry);
+                        changes.push((backend.delete_time_entry(args), undo));
+                    }
+                }
+
+                if changes.is_empty() {
+                    model.cell_changes.remove(&cell_key);
+                    return Some(())
+                }
+                for _ in &changes {
+                    model.changes_status.request_started();
+                }
+                orders.perform_cmd(async move {
+                    let mut results = Vec::new();
+                    for (future, undo) in changes {
+                        results.push((future.await.err(), undo));
+                    }
+                    Msg::CellSaved(cell_key, input, results)
+                });
+                Some(())
+            };
+            save_cell();
+        },
+        Msg::CellSaved(cell_key, input, results) => {
+            let mut saved = true;
+            for (error, undo) in results {
+                saved &= matches!(error, None | Some(graphql::GraphQLError::Queued));
+                common::changes_saved(error, Some(undo), &mut model.clients, &mut model.changes_status, &mut model.errors, orders);
+            }
+            // The input is kept when it couldn't be saved or when it has been changed in the meantime.
+            if saved && model.cell_changes.get(&cell_key) == Some(&input) {
+                model.cell_changes.remove(&cell_key);
+            }
+        },
+    }
+}
+
+// An empty cell means zero.
+fn parse_cell_duration(input: &str) -> parser::Result<Duration> {
+    let duration = if input.trim().is_empty() {
+        Duration::zero()
+    } else {
+        parser::parse_duration(input)?
+    };
+    if duration < Duration::zero() {
+        Err("The duration can't be negative.")?
+    }
+    Ok(duration)
+}
+
+// Sorted by start. Days with a running time entry can't be edited - its duration changes every second.
+fn stopped_day_time_entries(
+    project: &Project,
+    date: NaiveDate,
+) -> parser::Result<Vec<(DateTime<Local>, TimeEntryId, DateTime<Local>)>> {
+    let mut time_entries = project
+        .day_time_entries(date)
+        .map(|(time_entry_id, time_entry)| -> parser::Result<_> {
+            let stopped = time_entry.stopped.ok_or("Stop the running time entry first.")?;
+            Ok((time_entry.started, *time_entry_id, stopped))
+        })
+        .collect::<parser::Result<Vec<_>>>()?;
+    time_entries.sort();
+    Ok(time_entries)
+}
+
+// How many of the last time entries have to be deleted to remove the excess time (a negative `difference`).
+fn deleted_time_entry_count(time_entries: &[(DateTime<Local>, TimeEntryId, DateTime<Local>)], mut difference: Duration) -> usize {
+    let mut count = 0;
+    for (started, _, stopped) in time_entries.iter().rev() {
+        let time_entry_duration = *stopped - *started;
+        if difference >= Duration::zero() || time_entry_duration + difference > Duration::zero() {
+            break
+        }
+        difference = difference + time_entry_duration;
+        count += 1;
+    }
+    count
+}
+
+// ------ ------
+//     View
+// ------ ------
+
+pub fn view(model: &Model, _: &Context) -> Node<Msg> {
+    section![
+        h1![C!["title", "ml-6", "mt-6", "mb-5"],
+            "Timesheet",
+            common::view_changes_status(&model.changes_status),
+        ],
+        div![C!["columns", "is-centered"],
+            div![C!["column"],
+                common::view_errors(&model.errors, || Msg::ClearErrors),
+                view_week_navigation(model.week_start),
+                match &model.clients {
+                    RemoteData::NotAsked => {
+                        page::log_in_required::view()
+                    },
+                    RemoteData::Loading => {
+                        progress![C!["progress", "is-link", "mt-6"]]
+                    },
+                    RemoteData::Failed => empty![],
+                    RemoteData::Loaded(clients) => {
+                        view_grid(model, clients)
+                    }
+                }
+            ]
+        ]
+    ]
+}
+
+fn view_week_navigation(week_start: NaiveDate) -> Node<Msg> {
+    div![C!["level", "is-mobile"],
+        div![C!["buttons", "has-addons"],
+            button![C!["button"],
+                ev(Ev::Click, |_| Msg::PreviousWeek),
+                "‹ Previous",
+            ],
+            button![C!["button"],
+                ev(Ev::Click, |_| Msg::CurrentWeek),
+                "This week",
+            ],
+            button![C!["button"],
+                ev(Ev::Click, |_| Msg::NextWeek),
+                "Next ›",
+            ],
+        ],
+        div![C!["is-size-5"],
+            format!("{} – {}", week_start.format("%F"), (week_start + Duration::days(6)).format("%F")),
+        ],
+    ]
+}
+
+fn view_grid(model: &Model, clients: &BTreeMap<ClientId, Client>) -> Node<Msg> {
+    let days = model.days().collect::<Vec<_>>();
+    let rows = clients
+        .iter()
+        .rev()
+        .flat_map(|(client_id, client)| {
+            client.projects.iter().rev().map(move |(project_id, project)| (*client_id, client, *project_id, project))
+        })
+        .collect::<Vec<_>>();
+
+    let day_total = |date: NaiveDate| {
+        rows
+            .iter()
+            .map(|(_, _, _, project)| project.day_total(date))
+            .fold(Duration::zero(), Duration::add)
+    };
+    let week_total = days.iter().map(|date| day_total(*date)).fold(Duration::zero(), Duration::add);
+
+    div![C!["table-container", "box"],
+        table![C!["table", "is-fullwidth", "is-hoverable"],
+            thead![
+                tr![
+                    th!["Project"],
+                    days.iter().map(|date| th![C!["has-text-centered"], date.format("%a %-d").to_string()]),
+                    th![C!["has-text-right"], "Total"],
+                ],
+            ],
+            tbody![
+                rows.iter().map(|(client_id, client, project_id, project)| {
+                    view_row(model, &days, *client_id, client, *project_id, project)
+                }),
+            ],
+            tfoot![
+                tr![
+                    th!["Total"],
+                    days.iter().map(|date| th![C!["has-text-centered"], common::format_hours_and_minutes(&day_total(*date))]),
+                    th![C!["has-text-right"], common::format_hours_and_minutes(&week_total)],
+                ],
+            ],
+        ],
+    ]
+}
+
+fn view_row(
+    model: &Model,
+    days: &[NaiveDate],
+    client_id: ClientId,
+    client: &Client,
+    project_id: ProjectId,
+    project: &Project,
+) -> Node<Msg> {
+    let row_total = days.iter().map(|date| project.day_total(*date)).fold(Duration::zero(), Duration::add);
+
+    tr![
+        td![
+            div![C!["has-text-grey", "is-size-7"], &client.name],
+            div![&project.name],
+        ],
+        days.iter().map(|date| {
+            let date = *date;
+            let cell_key = (client_id, project_id, date);
+            let cell_error = model.cell_errors.get(&cell_key);
+            let value = model.cell_changes.get(&cell_key).cloned().unwrap_or_else(|| {
+                let total = project.day_total(date);
+                if total.is_zero() { String::new() } else { common::format_hours_and_minutes(&total) }
+            });
+            td![
+                input![C!["input", "is-small", "has-text-centered", IF!(cell_error.is_some() => "is-danger")],
+                    style!{St::MinWidth => rem(4)},
+                    attrs!{
+                        At::Value => value,
+                        At::Title => cell_error.map(String::as_str).unwrap_or("e.g. 1h30m, 1.5h or 1:30"),
+                    },
+                    input_ev(Ev::Input, move |duration| Msg::CellChanged(client_id, project_id, date, duration)),
+                    ev(Ev::Change, move |_| Msg::SaveCell(client_id, project_id, date)),
+                ],
+                cell_error.map(|error| p![C!["help", "is-danger"], error]),
+            ]
+        }),
+        td![C!["has-text-right", "has-text-weight-bold"], common::format_hours_and_minutes(&row_total)],
+    ]
+}
@@ -0,0 +1,320 @@
+use seed::{prelude::*, *};
+
+use chrono::{prelude::*, Duration};
+
+use std::convert::TryFrom;
+
+// Error messages are displayed directly under the input.
+pub type Result<T> = std::result::Result<T, String>;
+
+// Longer durations are typos - and they wouldn't fit into `Duration`.
+const MAX_DURATION_SECONDS: f64 = 1_000_000. * 3600.;
+
+// ------ ------
+//   Duration
+// ------ ------
+
+// Accepts e.g. `1h30m`, `1h 30m`, `1.5h`, `90m`, `45s`, `1:30`, `1:30:15` or `1.5` (hours).
+// A leading `-` makes the duration negative.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let error = || format!("\"{}\" is not a duration. Try e.g. 1h30m, 1.5h, 90m or 1:30.", input.trim());
+
+    let lowercase_input = input.trim().to_lowercase();
+    let (negative, text) = match lowercase_input.strip_prefix('-') {
+        Some(text) => (true, text.trim_start()),
+        None => (false, lowercase_input.as_str()),
+    };
+
+    let seconds = if text.contains(':') {
+        parse_clock_duration(text)
+    } else if not_empty_and_all(text, |character| character.is_ascii_digit() || character == '.') {
+        text.parse::<f64>().ok().map(|hours| hours * 3600.)
+    } else {
+        parse_unit_duration(text)
+    }.ok_or_else(error)?;
+
+    if not(seconds.is_finite()) || seconds > MAX_DURATION_SECONDS {
+        Err(error())?
+    }
+    let seconds = seconds.round() as i64;
+    Ok(Duration::seconds(if negative { -seconds } else { seconds }))
+}
+
+// `H:MM` or `H:MM:SS`
+fn parse_clock_duration(text: &str) -> Option<f64> {
+    let parts = parse_clock_parts(text)?;
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [hours, minutes] => (*hours, *minutes, 0),
+        [hours, minutes, seconds] => (*hours, *minutes, *seconds),
+        _ => None?,
+    };
+    if minutes >= 60 || seconds >= 60 {
+        None?
+    }
+    let seconds = hours.checked_mul(3600)?.checked_add(minutes * 60 + seconds)?;
+    Some(seconds as f64)
+}
+
+// `1h30m`, `1.5 hours`, `90 min`, `1h 5m 30s`, ...
+fn parse_unit_duration(text: &str) -> Option<f64> {
+    if text.is_empty() {
+        None?
+    }
+    let mut seconds = 0.;
+    let mut rest = text;
+    while not(rest.is_empty()) {
+        let number_end = rest
+            .find(|character: char| not(character.is_ascii_digit() || character == '.'))
+            .unwrap_or_else(|| rest.len());
+        let number = rest[..number_end].parse::<f64>().ok()?;
+        rest = rest[number_end..].trim_start();
+
+        let unit_end = rest
+            .find(|character: char| not(character.is_ascii_alphabetic()))
+            .unwrap_or_else(|| rest.len());
+        let unit_seconds = match &rest[..unit_end] {
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.,
+            _ => None?,
+        };
+        rest = rest[unit_end..].trim_start();
+
+        seconds += number * unit_seconds;
+    }
+    Some(seconds)
+}
+
+// ------ ------
+//     Time
+// ------ ------
+
+// Accepts e.g. `14:05`, `14:05:30`, `14`, `9am` or `9:30 pm`.
+pub fn parse_time(input: &str) -> Result<NaiveTime> {
+    let error = || format!("\"{}\" is not a time. Try e.g. 9am, 14:05 or 14:05:30.", input.trim());
+
+    let lowercase_input = input.trim().to_lowercase();
+    let (text, pm) = if let Some(text) = lowercase_input.strip_suffix("am") {
+        (text.trim_end(), Some(false))
+    } else if let Some(text) = lowercase_input.strip_suffix("pm") {
+        (text.trim_end(), Some(true))
+    } else {
+        (lowercase_input.as_str(), None)
+    };
+
+    let parts = parse_clock_parts(text).ok_or_else(error)?;
+    let (mut hour, minute, second) = match parts.as_slice() {
+        [hour] => (*hour, 0, 0),
+        [hour, minute] => (*hour, *minute, 0),
+        [hour, minute, second] => (*hour, *minute, *second),
+        _ => Err(error())?,
+    };
+    if let Some(pm) = pm {
+        if hour == 0 || hour > 12 {
+            Err(error())?
+        }
+        hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+    let to_u32 = |number: i64| u32::try_from(number).map_err(|_| error());
+    NaiveTime::from_hms_opt(to_u32(hour)?, to_u32(minute)?, to_u32(second)?).ok_or_else(error)
+}
+
+// ------ ------
+//     Date
+// ------ ------
+
+// Accepts e.g. `2020-01-15`, `today`, `yesterday`, `tomorrow`
+// or a weekday like `mon` or `monday` (the last one, including today).
+pub fn parse_date(input: &str) -> Result<NaiveDate> {
+    let error = || format!("\"{}\" is not a date. Try e.g. 2020-01-15, yesterday or mon.", input.trim());
+
+    let text = input.trim().to_lowercase();
+    let today = Local::today().naive_local();
+
+    match text.as_str() {
+        "today" => return Ok(today),
+        "yesterday" => return Ok(today.pred()),
+        "tomorrow" => return Ok(today.succ()),
+        _ => (),
+    }
+    if let Ok(weekday) = text.parse::<Weekday>() {
+        let days_back = (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+        return Ok(today - Duration::days(i64::from(days_back)))
+    }
+    NaiveDate::parse_from_str(&text, "%F").map_err(|_| error())
+}
+
+// ------ ------
+//   DateTime
+// ------ ------
+
+pub fn local_date_time(date: NaiveDate, time: NaiveTime) -> Result<DateTime<Local>> {
+    Local
+        .from_local_date(&date)
+        .and_time(time)
+        .single()
+        .ok_or_else(|| format!("{} {} does not exist in the local time zone.", date, time))
+}
+
+// The start of the day - unlike `local_date_time` it always exists.
+pub fn local_midnight(date: NaiveDate) -> DateTime<Local> {
+    let midnight = date.and_hms(0, 0, 0);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        // Midnight doesn't exist when DST starts at midnight - the day starts an hour later.
+        .or_else(|| Local.from_local_datetime(&(midnight + Duration::hours(1))).earliest())
+        .unwrap_or_else(|| Local.from_utc_datetime(&midnight))
+}
+
+// Accepts RFC 3339 (e.g. `2020-01-15T15:53:39Z`) or a local date and time
+// like `2020-01-15 15:53:39`, `01/15/2020 03:53 PM` or `15.01.2020 15:53`.
+pub fn parse_date_time(input: &str) -> Result<DateTime<Local>> {
+    const FORMATS: [&str; 10] = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%m/%d/%Y %H:%M:%S",
+        "%m/%d/%Y %H:%M",
+        "%m/%d/%Y %I:%M:%S %p",
+        "%m/%d/%Y %I:%M %p",
+        "%d.%m.%Y %H:%M:%S",
+        "%d.%m.%Y %H:%M",
+    ];
+    let text = input.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(date_time.with_timezone(&Local))
+    }
+    let date_time = FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .ok_or_else(|| format!("\"{}\" is not a date and time. Try e.g. 2020-01-15 14:05.", text))?;
+    local_date_time(date_time.date(), date_time.time())
+}
+
+// ------ ------
+//    Helpers
+// ------ ------
+
+fn parse_clock_parts(text: &str) -> Option<Vec<i64>> {
+    text
+        .split(':')
+        .map(|part| {
+            if not_empty_and_all(part, |character| character.is_ascii_digit()) {
+                part.parse().ok()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn not_empty_and_all(text: &str, predicate: impl Fn(char) -> bool) -> bool {
+    not(text.is_empty()) && text.chars().all(predicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_units_clock_and_hours() {
+        let seconds = |input| parse_duration(input).map(|duration| duration.num_seconds());
+
+        assert_eq!(seconds("1h30m"), Ok(5400));
+        assert_eq!(seconds("1h 5m 30s"), Ok(3930));
+        assert_eq!(seconds("1.5 hours"), Ok(5400));
+        assert_eq!(seconds("90 min"), Ok(5400));
+        assert_eq!(seconds("45s"), Ok(45));
+        assert_eq!(seconds("1:30"), Ok(5400));
+        assert_eq!(seconds("1:30:15"), Ok(5415));
+        assert_eq!(seconds("1.5"), Ok(5400));
+        assert_eq!(seconds(" -0:10 "), Ok(-600));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid_and_too_large_input() {
+        for input in &[
+            "", "abc", "1x", "1:60", "1:00:60", "1::00", ".",
+            "9999999999999999:00", "9223372036854775807:00",
+            "1000001h", "-1000001h", "99999999999",
+        ] {
+            assert!(parse_duration(input).is_err(), "{:?}", input);
+        }
+        assert!(parse_duration(&"9".repeat(400)).is_err());
+    }
+
+    #[test]
+    fn parse_time_accepts_24_hour_and_am_pm() {
+        let time = |hour, minute, second| Ok(NaiveTime::from_hms_opt(hour, minute, second).unwrap());
+
+        assert_eq!(parse_time("14:05"), time(14, 5, 0));
+        assert_eq!(parse_time("14:05:30"), time(14, 5, 30));
+        assert_eq!(parse_time("14"), time(14, 0, 0));
+        assert_eq!(parse_time("9am"), time(9, 0, 0));
+        assert_eq!(parse_time("9:30 PM"), time(21, 30, 0));
+        assert_eq!(parse_time("12am"), time(0, 0, 0));
+        assert_eq!(parse_time("12pm"), time(12, 0, 0));
+    }
+
+    #[test]
+    fn parse_time_rejects_invalid_input() {
+        for input in &["", "am", "0am", "13pm", "24:00", "14:60", "1:2:3:4", "4294967305", "9 o'clock"] {
+            assert!(parse_time(input).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn parse_date_accepts_iso_dates_and_relative_days() {
+        let today = Local::today().naive_local();
+
+        assert_eq!(parse_date("2020-01-15"), Ok(NaiveDate::from_ymd_opt(2020, 1, 15).unwrap()));
+        assert_eq!(parse_date("Today"), Ok(today));
+        assert_eq!(parse_date("yesterday"), Ok(today.pred()));
+        assert_eq!(parse_date("tomorrow"), Ok(today.succ()));
+        assert!(parse_date("2020-02-30").is_err());
+        assert!(parse_date("someday").is_err());
+    }
+
+    #[test]
+    fn parse_date_time_accepts_rfc_3339_and_common_local_formats() {
+        let local = |year, month, day, hour, minute| {
+            local_date_time(NaiveDate::from_ymd_opt(year, month, day).unwrap(), NaiveTime::from_hms_opt(hour, minute, 0).unwrap())
+        };
+
+        assert_eq!(parse_date_time("2020-01-15T14:05:00Z"), Ok(Utc.ymd(2020, 1, 15).and_hms(14, 5, 0).with_timezone(&Local)));
+        assert_eq!(parse_date_time("2020-01-15 14:05"), local(2020, 1, 15, 14, 5));
+        assert_eq!(parse_date_time("01/15/2020 02:05 PM"), local(2020, 1, 15, 14, 5));
+        assert_eq!(parse_date_time("15.01.2020 14:05"), local(2020, 1, 15, 14, 5));
+        for input in &["", "2020-01-15", "15.01.2020 14", "2020-13-01 10:00"] {
+            assert!(parse_date_time(input).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn parse_date_returns_the_last_weekday_including_today() {
+        let today = Local::today().naive_local();
+
+        for input in &["mon", "tuesday", "wed", "thu", "fri", "saturday", "sun"] {
+            let date = parse_date(input).unwrap();
+            assert_eq!(date.weekday(), input.parse::<Weekday>().unwrap(), "{:?}", input);
+            assert!(date <= today && today - date < Duration::days(7), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn local_midnight_starts_the_given_day() {
+        // Includes DST changes - e.g. in Brazil DST started at midnight on 2018-11-04.
+        for date in &[
+            NaiveDate::from_ymd(2018, 11, 4), 
+            NaiveDate::from_ymd(2021, 3, 28), 
+            NaiveDate::from_ymd(2021, 10, 31), 
+            NaiveDate::from_ymd(2021, 12, 31),
+        ] {
+            let midnight = local_midnight(*date);
+            assert_eq!(midnight.naive_local().date(), *date);
+            assert!(midnight.naive_local().time() <= NaiveTime::from_hms(1, 0, 0));
+        }
+    }
+}